@@ -0,0 +1,132 @@
+use std::ops::Range;
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::error::*;
+use crate::reader::*;
+use crate::winpe::WinPe;
+
+pub const WIN_CERT_REVISION_1_0: u16 = 0x0100;
+pub const WIN_CERT_REVISION_2_0: u16 = 0x0200;
+
+pub const WIN_CERT_TYPE_X509: u16 = 0x0001;
+pub const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+pub const WIN_CERT_TYPE_RESERVED_1: u16 = 0x0003;
+pub const WIN_CERT_TYPE_TS_STACK_SIGNED: u16 = 0x0004;
+
+/// offset of CheckSum in the optional header, same for PE32 and PE32+
+pub(crate) const CHECKSUM_OFFSET: usize = 64;
+pub(crate) const CERTIFICATE_DIRECTORY_INDEX: usize = 4;
+
+/// WIN_CERTIFICATE entry of the certificate table
+#[derive(Debug)]
+pub struct WinCertificate<'a> {
+    pub length: u32,
+    pub revision: u16,
+    pub certificate_type: u16,
+    /// PKCS#7 SignedData blob for WIN_CERT_TYPE_PKCS_SIGNED_DATA
+    pub data: &'a [u8],
+}
+
+impl<'a> WinCertificate<'a> {
+    pub fn is_pkcs7(&self) -> bool {
+        self.certificate_type == WIN_CERT_TYPE_PKCS_SIGNED_DATA
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+}
+
+/// certificate table location, the directory holds a file offset rather than an rva
+fn certificate_table(pe: &WinPe, len: usize) -> Result<Option<Range<usize>>> {
+    let dir = &pe.certificate_tbl;
+    if dir.rva == 0 || dir.size == 0 {
+        return Ok(None);
+    }
+    let start = dir.rva as usize;
+    let end = start + dir.size as usize;
+    if end > len {
+        return Err(Error::Truncated { offset: start });
+    }
+    Ok(Some(start..end))
+}
+
+/// every WIN_CERTIFICATE in the certificate table of `data`
+pub fn parse_certificates<'a>(pe: &WinPe, data: &'a [u8]) -> Result<Vec<WinCertificate<'a>>> {
+    let table = match certificate_table(pe, data.len())? {
+        Some(t) => t,
+        None => return Ok(Vec::new()),
+    };
+    let reader = &mut BinaryReader::new(data).sub_reader(table.start, table.len())?;
+
+    let mut certs = Vec::new();
+    while reader.remaining() >= 8 {
+        let start = reader.pos;
+        let length = reader.le_u32()?;
+        let revision = reader.le_u16()?;
+        let certificate_type = reader.le_u16()?;
+        if length < 8 {
+            return Err(Error::InvalidValue { what: "WIN_CERTIFICATE length", value: length });
+        }
+        let data = reader.bytes(length as usize - 8)?;
+        certs.push(WinCertificate {
+            length,
+            revision,
+            certificate_type,
+            data,
+        });
+        //entries are quadword aligned
+        let next = (start + length as usize + 7) & !7;
+        reader.seek(next.min(reader.raw_data.len()));
+    }
+    Ok(certs)
+}
+
+/// byte ranges of the image covered by the authenticode digest: everything except
+/// the checksum field, the certificate table directory entry and the certificate table
+pub fn authenticode_ranges(pe: &WinPe, len: usize) -> Result<Vec<Range<usize>>> {
+    let checksum = pe.opt_header_offset + CHECKSUM_OFFSET;
+    if checksum + 4 > len {
+        return Err(Error::Truncated { offset: checksum });
+    }
+    //short directory array without a certificate table entry, only the checksum is skipped
+    if pe.num_rva_and_sizes as usize <= CERTIFICATE_DIRECTORY_INDEX {
+        return Ok(vec![0..checksum, checksum + 4..len]);
+    }
+    let cert_entry = pe.data_directory_offset + CERTIFICATE_DIRECTORY_INDEX * 8;
+    if cert_entry + 8 > len {
+        return Err(Error::Truncated { offset: cert_entry });
+    }
+    let end = match certificate_table(pe, len)? {
+        Some(t) => t.start,
+        None => len,
+    };
+
+    Ok(vec![
+        0..checksum,
+        checksum + 4..cert_entry,
+        cert_entry + 8..end,
+    ])
+}
+
+/// authenticode image hash of `data`, compare it with the digest in the signer's SpcIndirectDataContent
+pub fn authenticode_digest(pe: &WinPe, data: &[u8], alg: DigestAlgorithm) -> Result<Vec<u8>> {
+    let ranges = authenticode_ranges(pe, data.len())?;
+    let digest = match alg {
+        DigestAlgorithm::Sha1 => hash_ranges::<Sha1>(data, &ranges),
+        DigestAlgorithm::Sha256 => hash_ranges::<Sha256>(data, &ranges),
+    };
+    Ok(digest)
+}
+
+pub(crate) fn hash_ranges<D: Digest>(data: &[u8], ranges: &[Range<usize>]) -> Vec<u8> {
+    let mut hasher = D::new();
+    for r in ranges {
+        hasher.update(&data[r.clone()]);
+    }
+    hasher.finalize().to_vec()
+}
@@ -5,42 +5,51 @@ use std::mem::transmute;
 use crate::il::*;
 use crate::reflection::*;
 
-pub struct Context {
-    pub reflection: ReflectionInfo,
+pub struct Context<'a> {
+    pub reflection: ReflectionInfo<'a>,
 
 }
 
-impl Context {
-    pub fn new() -> Context {
+impl<'a> Context<'a> {
+    pub fn new() -> Context<'a> {
         Context {
             reflection: ReflectionInfo::new()
         }
     }
 
     pub fn exec(&self, method_info: &MethodInfo,args:Option<Vec<Data>>) -> Option<Data> {
-        let mut stack: ExecStack = Default::default();
-
-        let inst = &method_info.instruction.borrow().instruction;
-        stack.exec(inst,args)
+        let method_impl = method_info.instruction.borrow();
+        //tiny headers and unresolved signatures keep the default slots
+        let mut stack = if method_impl.locals.is_empty() {
+            ExecStack::default()
+        } else {
+            ExecStack::with_locals(method_impl.locals.len())
+        };
+        stack.exec(&method_impl.instruction,args)
     }
 }
 
 #[derive(Debug)]
 pub struct ExecStack {
     pub stack: Vec<Data>,
-    pub local:[Data;8],
+    /// one slot per local of the method's LocalVarSig, stloc.0 adds slot 0 when the list is empty
+    pub local:Vec<Data>,
 }
 
 impl Default for ExecStack{
     fn default() -> Self {
+        ExecStack::with_locals(8)
+    }
+}
+
+impl ExecStack {
+    pub fn with_locals(count: usize) -> ExecStack {
         ExecStack{
             stack:Vec::new(),
-            local:[Data::none();8],
+            local:vec![Data::none();count],
         }
     }
-}
 
-impl ExecStack {
     #[allow(unused_assignments)]
     pub fn exec(&mut self, instructions: &Vec<Instruction>,args:Option<Vec<Data>>) -> Option<Data> {
         //TODO: need to check the args is match method parameters
@@ -66,10 +75,13 @@ impl ExecStack {
                 }
                 OpCode::stloc_0=>{
                     let val = stack.pop().unwrap();
+                    if self.local.is_empty() {
+                        self.local.push(Data::none());
+                    }
                     self.local[0] = val;
                 }
                 OpCode::ldloc_0=>{
-                    stack.push(self.local[0]);
+                    stack.push(self.local.first().copied().unwrap_or(Data::none()));
                 }
                 OpCode::br_s=>{
                     let ptr:*const i8 = unsafe{ transmute(&il.data)};
@@ -1,2 +1,45 @@
+use std::fmt;
 
+/// 16 byte guid in its on-disk (mixed endian) layout
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Guid(pub [u8; 16]);
 
+impl Guid {
+    pub fn data1(&self) -> u32 {
+        let b = &self.0;
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+    }
+
+    pub fn data2(&self) -> u16 {
+        u16::from_le_bytes([self.0[4], self.0[5]])
+    }
+
+    pub fn data3(&self) -> u16 {
+        u16::from_le_bytes([self.0[6], self.0[7]])
+    }
+
+    pub fn data4(&self) -> &[u8] {
+        &self.0[8..]
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.0.iter().all(|&b| b == 0)
+    }
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let d4 = self.data4();
+        write!(f, "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-", self.data1(), self.data2(), self.data3(), d4[0], d4[1])?;
+        for b in &d4[2..] {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Guid({})", self)
+    }
+}
@@ -0,0 +1,182 @@
+use std::str;
+
+use crate::data::Guid;
+use crate::error::*;
+use crate::reader::*;
+use crate::winpe::WinPe;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugType {
+    Unknown = 0,
+    Coff = 1,
+    CodeView = 2,
+    Fpo = 3,
+    Misc = 4,
+    Exception = 5,
+    Fixup = 6,
+    Borland = 9,
+    Clsid = 11,
+    Reproducible = 16,
+    EmbeddedPortablePdb = 17,
+    PdbChecksum = 19,
+}
+
+impl From<u32> for DebugType {
+    fn from(v: u32) -> Self {
+        match v {
+            1 => DebugType::Coff,
+            2 => DebugType::CodeView,
+            3 => DebugType::Fpo,
+            4 => DebugType::Misc,
+            5 => DebugType::Exception,
+            6 => DebugType::Fixup,
+            9 => DebugType::Borland,
+            11 => DebugType::Clsid,
+            16 => DebugType::Reproducible,
+            17 => DebugType::EmbeddedPortablePdb,
+            19 => DebugType::PdbChecksum,
+            _ => DebugType::Unknown,
+        }
+    }
+}
+
+/// IMAGE_DEBUG_DIRECTORY entry
+#[derive(Debug)]
+pub struct DebugDirectoryEntry<'a> {
+    pub characteristics: u32,
+    pub time_date_stamp: u32,
+    pub major_ver: u16,
+    pub minor_ver: u16,
+    pub debug_type: u32,
+    pub size_of_data: u32,
+    pub addr_of_raw_data: u32,
+    pub pointer_to_raw_data: u32,
+    pub data: &'a [u8],
+    pub info: DebugInfo<'a>,
+}
+
+#[derive(Debug)]
+pub enum DebugInfo<'a> {
+    CodeView(CodeViewInfo<'a>),
+    /// deterministic build marker, data is empty or the hash the build was keyed on
+    Reproducible(&'a [u8]),
+    /// deflate compressed portable pdb
+    EmbeddedPortablePdb { uncompressed_size: u32, compressed: &'a [u8] },
+    PdbChecksum { algorithm: &'a str, checksum: &'a [u8] },
+    Other,
+}
+
+/// CodeView RSDS record
+#[derive(Debug)]
+pub struct CodeViewInfo<'a> {
+    pub guid: Guid,
+    pub age: u32,
+    pub path: &'a str,
+}
+
+impl<'a> CodeViewInfo<'a> {
+    fn parse(reader: &mut BinaryReader<'a>) -> Result<CodeViewInfo<'a>> {
+        reader.expect_tag(b"RSDS")?;
+        let guid = reader.guid()?;
+        let age = reader.le_u32()?;
+        let path = utf8(reader)?;
+        Ok(CodeViewInfo { guid, age, path })
+    }
+
+    /// key used by symbol servers to index the pdb, guid without dashes followed by the age
+    pub fn symbol_key(&self) -> String {
+        format!("{}{:X}", self.guid.to_string().replace('-', "").to_uppercase(), self.age)
+    }
+}
+
+impl<'a> DebugDirectoryEntry<'a> {
+    pub fn get_type(&self) -> DebugType {
+        DebugType::from(self.debug_type)
+    }
+
+    /// codeview entry that points at a portable pdb instead of a windows pdb
+    pub fn is_portable_pdb(&self) -> bool {
+        self.get_type() == DebugType::CodeView && self.minor_ver == 0x504D
+    }
+
+    /// entry at the position of `reader`, its raw data is looked up in `image`
+    fn parse(reader: &mut BinaryReader, image: &BinaryReader<'a>, pe: &WinPe) -> Result<DebugDirectoryEntry<'a>> {
+        let characteristics = reader.le_u32()?;
+        let time_date_stamp = reader.le_u32()?;
+        let major_ver = reader.le_u16()?;
+        let minor_ver = reader.le_u16()?;
+        let debug_type = reader.le_u32()?;
+        let size_of_data = reader.le_u32()?;
+        let addr_of_raw_data = reader.le_u32()?;
+        let pointer_to_raw_data = reader.le_u32()?;
+
+        let data_pos = if pointer_to_raw_data != 0 {
+            pointer_to_raw_data as usize
+        } else if addr_of_raw_data != 0 {
+            pe.rva_to_offset(addr_of_raw_data)?
+        } else {
+            0
+        };
+        //raw data may live anywhere in the image
+        let data_reader = &mut image.sub_reader(data_pos, size_of_data as usize)?;
+        let data = data_reader.raw_data;
+
+        let info = match DebugType::from(debug_type) {
+            //older formats such as NB10 are left undecoded
+            DebugType::CodeView if data.starts_with(b"RSDS") => DebugInfo::CodeView(CodeViewInfo::parse(data_reader)?),
+            DebugType::Reproducible => DebugInfo::Reproducible(data),
+            DebugType::EmbeddedPortablePdb => {
+                data_reader.expect_tag(b"MPDB")?;
+                let uncompressed_size = data_reader.le_u32()?;
+                DebugInfo::EmbeddedPortablePdb {
+                    uncompressed_size,
+                    compressed: &data[data_reader.pos..],
+                }
+            }
+            DebugType::PdbChecksum => {
+                let algorithm = utf8(data_reader)?;
+                DebugInfo::PdbChecksum {
+                    algorithm,
+                    checksum: &data[data_reader.pos..],
+                }
+            }
+            _ => DebugInfo::Other,
+        };
+
+        Ok(DebugDirectoryEntry {
+            characteristics,
+            time_date_stamp,
+            major_ver,
+            minor_ver,
+            debug_type,
+            size_of_data,
+            addr_of_raw_data,
+            pointer_to_raw_data,
+            data,
+            info,
+        })
+    }
+}
+
+fn utf8<'a>(reader: &mut BinaryReader<'a>) -> Result<&'a str> {
+    let offset = reader.abs_pos();
+    str::from_utf8(reader.c_str()?).map_err(|_| Error::InvalidUtf8 { offset })
+}
+
+/// parse every entry of the debug data directory of `data`, the image `pe` was read from
+pub fn parse_debug_directory<'a>(pe: &WinPe, data: &'a [u8]) -> Result<Vec<DebugDirectoryEntry<'a>>> {
+    let dir = &pe.debug;
+    if dir.rva == 0 || dir.size == 0 {
+        return Ok(Vec::new());
+    }
+    let pos = pe.rva_to_offset(dir.rva)?;
+    let image = &BinaryReader::new(data);
+    let directory = &mut image.sub_reader(pos, dir.size as usize)?;
+
+    let count = dir.size / 28;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        entries.push(DebugDirectoryEntry::parse(directory, image, pe)?);
+    }
+    Ok(entries)
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+use std::io;
+use std::result;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// magic bytes (MZ, PE, BSJB, ...) not found where expected
+    BadSignature { offset: usize, expected: &'static [u8] },
+    /// data ends before the structure at offset is complete
+    Truncated { offset: usize },
+    InvalidUtf8 { offset: usize },
+    UnknownOpcode { offset: usize, opcode: u8 },
+    /// value of a field is outside the range the spec allows
+    InvalidValue { what: &'static str, value: u32 },
+    Unsupported(&'static str),
+    /// rva is not covered by any section of the image
+    InvalidRva(u32),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::BadSignature { offset, expected } => write!(f, "expect signature {:x?} at {:#x}", expected, offset),
+            Error::Truncated { offset } => write!(f, "unexpected end of data at {:#x}", offset),
+            Error::InvalidUtf8 { offset } => write!(f, "invalid utf-8 string at {:#x}", offset),
+            Error::UnknownOpcode { offset, opcode } => write!(f, "unknown opcode {:#x} at {:#x}", opcode, offset),
+            Error::InvalidValue { what, value } => write!(f, "invalid {}: {:#x}", what, value),
+            Error::Unsupported(what) => write!(f, "unsupported: {}", what),
+            Error::InvalidRva(rva) => write!(f, "rva {:#x} is not mapped by any section", rva),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
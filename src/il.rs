@@ -1,10 +1,13 @@
 #![allow(non_camel_case_types)]
 
+use std::convert::TryFrom;
 use std::intrinsics::transmute;
 use std::mem::size_of;
 use std::fmt;
 
+use crate::error::*;
 use crate::reader::BinaryReader;
+use crate::tbl::MetadataToken;
 
 #[derive(Copy, Clone, Debug)]
 pub enum OpCode {
@@ -19,11 +22,28 @@ pub enum OpCode {
     ldloc_1 = 0x07,
     ret = 0x2A,
     call = 0x28,
+    ldstr = 0x72,
 }
 
-impl From<u8> for OpCode {
-    fn from(v: u8) -> OpCode {
-        unsafe { transmute(v) }
+impl TryFrom<u8> for OpCode {
+    type Error = u8;
+
+    fn try_from(v: u8) -> std::result::Result<OpCode, u8> {
+        match v {
+            0x0 => Ok(OpCode::nop),
+            0x02 => Ok(OpCode::ldarg_0),
+            0x03 => Ok(OpCode::ldarg_1),
+            0x58 => Ok(OpCode::add),
+            0x20 => Ok(OpCode::ldc_i4),
+            0x0A => Ok(OpCode::stloc_0),
+            0x2B => Ok(OpCode::br_s),
+            0x06 => Ok(OpCode::ldloc_0),
+            0x07 => Ok(OpCode::ldloc_1),
+            0x2A => Ok(OpCode::ret),
+            0x28 => Ok(OpCode::call),
+            0x72 => Ok(OpCode::ldstr),
+            _ => Err(v),
+        }
     }
 }
 
@@ -42,6 +62,7 @@ pub union Data {
     pub f32: f32,
     pub f64: f64,
     pub bool: bool,
+    pub token: MetadataToken,
     pub data_ref:DataRefType,
 }
 
@@ -62,6 +83,10 @@ impl Data{
     pub fn to_i64(&self)->i64{
         unsafe {self.i64}
     }
+    #[inline]
+    pub fn to_token(&self)->MetadataToken{
+        unsafe {self.token}
+    }
 }
 
 
@@ -100,25 +125,30 @@ pub struct Instruction {
 }
 
 
-pub fn parse_il_instructions(reader: &mut BinaryReader, count: u32) -> ( Vec<Instruction>,u8) {
+pub fn parse_il_instructions(reader: &mut BinaryReader, count: u32) -> Result<(Vec<Instruction>,u8)> {
     let mut set = Vec::new();
     let pos_max = reader.pos + count as usize;
+    if pos_max > reader.raw_data.len() {
+        return Err(Error::Truncated { offset: reader.raw_data.len() });
+    }
 
     let mut param_list_len:u8 = 0;
     while reader.pos < pos_max {
-        let code = reader.le_u8();
-        let op = OpCode::from(code);
+        let offset = reader.pos;
+        let code = reader.le_u8()?;
+        let op = match OpCode::try_from(code) {
+            Ok(op) => op,
+            Err(opcode) => return Err(Error::UnknownOpcode { offset, opcode }),
+        };
         let instruction = match op {
             OpCode::nop => Instruction { op, data: Data::none() },
-            OpCode::ldc_i4 => Instruction { op, data: Data { i32: reader.le_i32() } },
+            OpCode::ldc_i4 => Instruction { op, data: Data { i32: reader.le_i32()? } },
             OpCode::stloc_0 => Instruction { op, data: Data::none() },
-            OpCode::br_s => Instruction { op, data: Data { i8: reader.le_i8() } },
+            OpCode::br_s => Instruction { op, data: Data { i8: reader.le_i8()? } },
             OpCode::ldloc_0 => Instruction { op, data: Data::none() },
             OpCode::ret => Instruction { op, data: Data::none() },
-            OpCode::call => {
-                let (_tag, tbl_ind) = reader.tag_index();
-                Instruction { op, data: Data { i32: tbl_ind as i32 } }
-            }
+            OpCode::call => Instruction { op, data: Data { token: MetadataToken(reader.le_u32()?) } },
+            OpCode::ldstr => Instruction { op, data: Data { token: MetadataToken(reader.le_u32()?) } },
             OpCode::ldarg_0 =>{
                 param_list_len = 1;
                 Instruction { op, data: Data::none() }
@@ -132,5 +162,5 @@ pub fn parse_il_instructions(reader: &mut BinaryReader, count: u32) -> ( Vec<Ins
         };
         set.push(instruction);
     }
-    (set,param_list_len)
+    Ok((set,param_list_len))
 }
\ No newline at end of file
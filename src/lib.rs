@@ -7,15 +7,24 @@
 extern crate lazy_static;
 
 pub mod il;
+pub mod authenticode;
+pub mod error;
 pub mod util;
 pub mod data;
 pub mod context;
+pub mod debugdir;
 pub mod loader;
 pub mod meta;
 pub mod tbl;
+pub mod pdb;
 pub mod reader;
+pub mod readytorun;
 pub mod reflection;
+pub mod rsrc;
+pub mod strongname;
 pub mod winpe;
 
+pub use crate::error::{Error, Result};
+
 #[cfg(test)]
 pub mod test;
\ No newline at end of file
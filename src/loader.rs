@@ -1,47 +1,198 @@
 use std::path::Path;
-use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::ops::Deref;
 
+use crate::authenticode::*;
+use crate::data::Guid;
+use crate::debugdir::*;
+use crate::error::*;
 use crate::meta::*;
+use crate::pdb::PdbFile;
 use crate::reader::*;
+use crate::readytorun::*;
+use crate::rsrc::*;
+use crate::strongname::*;
+use crate::tbl::MetadataToken;
 use crate::winpe::WinPe;
 
-pub fn load_dll(file_path:&str)->DllFile{
+pub fn load_dll(file_path:&str)->Result<DllFile<'static>>{
     let path = Path::new(file_path);
-    let mut file = match File::open(&path) {
-        Err(e)=> panic!("can not open file: {}", e.description()),
-        Ok(file)=> file,
-    };
+    let file = File::open(&path)?;
+    DllFile::from_reader(file)
+}
+
+/// image bytes backing a DllFile
+pub enum DllData<'a>{
+    Borrowed(&'a [u8]),
+    Owned(Vec<u8>),
+    /// any owner of the bytes, e.g. a memory mapped file
+    Mapped(Box<dyn AsRef<[u8]> + 'a>),
+}
+
+impl<'a> Deref for DllData<'a>{
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            DllData::Borrowed(dat)=>dat,
+            DllData::Owned(dat)=>dat,
+            DllData::Mapped(dat)=>(**dat).as_ref(),
+        }
+    }
+}
 
-    let mut data = vec![];
-    let _result = match file.read_to_end(&mut data) {
-        Err(_e)=> panic!("read file failed: {}",_e.description()),
-        Ok(size)=>{size},
-    };
-    DllFile::new(data)
+impl<'a> Default for DllData<'a>{
+    fn default() -> Self {
+        DllData::Owned(Vec::new())
+    }
+}
+
+impl<'a> fmt::Debug for DllData<'a>{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            DllData::Borrowed(_)=>"Borrowed",
+            DllData::Owned(_)=>"Owned",
+            DllData::Mapped(_)=>"Mapped",
+        };
+        write!(f,"DllData::{}({} bytes)",kind,self.len())
+    }
 }
 
 #[derive(Debug,Default)]
-pub struct DllFile{
-    pub data:Vec<u8>,
+pub struct DllFile<'a>{
+    pub data:DllData<'a>,
+    pub pe:WinPe,
     pub clidata:Box<CLIData>,
 }
 
-impl DllFile{
-    pub fn new(dat:Vec<u8>)->DllFile{
+impl DllFile<'static>{
+    pub fn new(dat:Vec<u8>)->Result<DllFile<'static>>{
+        DllFile::parse(DllData::Owned(dat))
+    }
+
+    /// copy the rest of the stream into an owned buffer and parse that,
+    /// use `from_bytes` or `from_mapped` to parse without copying
+    pub fn from_reader<R:Read + Seek>(mut reader:R)->Result<DllFile<'static>>{
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+
+        let mut data = Vec::with_capacity(end.saturating_sub(start) as usize);
+        reader.read_to_end(&mut data)?;
+        DllFile::new(data)
+    }
+}
+
+impl<'a> DllFile<'a>{
+    /// parse an image in place, the returned DllFile borrows `dat`
+    pub fn from_bytes(dat:&'a [u8])->Result<DllFile<'a>>{
+        DllFile::parse(DllData::Borrowed(dat))
+    }
+
+    /// parse an image from any owner of its bytes, such as a memory map, without copying
+    pub fn from_mapped<M:AsRef<[u8]> + 'a>(dat:M)->Result<DllFile<'a>>{
+        DllFile::parse(DllData::Mapped(Box::new(dat)))
+    }
 
+    fn parse(dat:DllData<'a>)->Result<DllFile<'a>>{
         let reader = &mut BinaryReader::new(&dat);
         reader.seek(0);
 
 
-        let pe = WinPe::parse_winpe(reader);
-        reader.ate(16);
+        let pe = WinPe::parse_winpe(reader)?;
+        let cli_header_pos = pe.rva_to_offset(pe.clr_runtime_header.rva)?;
+        reader.seek(cli_header_pos);
 
-        let cli = Box::new(CLIData::parse_cli_data(reader,&pe));
-        DllFile{
+        let cli = Box::new(CLIData::parse_cli_data(reader,&pe)?);
+        Ok(DllFile{
             data:dat,
+            pe,
             clidata:cli
+        })
+    }
+
+    #[inline]
+    pub fn bytes(&self)->&[u8]{
+        &self.data
+    }
+
+    pub fn debug_directory(&self)->Result<Vec<DebugDirectoryEntry>>{
+        parse_debug_directory(&self.pe,self.bytes())
+    }
+
+    /// portable pdb compressed into the debug directory, None when the image carries none
+    pub fn embedded_pdb(&self)->Result<Option<PdbFile<'static>>>{
+        for entry in self.debug_directory()? {
+            if let DebugInfo::EmbeddedPortablePdb{uncompressed_size,compressed} = entry.info {
+                return Ok(Some(PdbFile::from_embedded(uncompressed_size,compressed)?));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn certificates(&self)->Result<Vec<WinCertificate>>{
+        parse_certificates(&self.pe,self.bytes())
+    }
+
+    pub fn authenticode_digest(&self,alg:DigestAlgorithm)->Result<Vec<u8>>{
+        authenticode_digest(&self.pe,self.bytes(),alg)
+    }
+
+    pub fn strong_name_signature(&self)->Result<Option<&[u8]>>{
+        strong_name_signature(&self.pe,&self.clidata,self.bytes())
+    }
+
+    pub fn strong_name_status(&self)->Result<StrongNameStatus>{
+        verify_strong_name(&self.pe,&self.clidata,self.bytes())
+    }
+
+    pub fn ready_to_run(&self)->Result<Option<ReadyToRunHeader>>{
+        parse_ready_to_run(&self.pe,&self.clidata,self.bytes())
+    }
+
+    /// precompiled by crossgen, the IL is still there but the runtime may skip it
+    pub fn is_ready_to_run(&self)->Result<bool>{
+        Ok(self.ready_to_run()?.is_some())
+    }
+
+    pub fn mvid(&self)->Option<Guid>{
+        self.clidata.mvid()
+    }
+
+    /// identifier at a #Strings offset, e.g. a name column of a table row
+    pub fn string(&self,offset:u32)->Result<&str>{
+        self.clidata.get_string(self.bytes(),offset)
+    }
+
+    /// literal at a raw #US offset
+    pub fn user_string(&self,offset:u32)->Result<UserString>{
+        self.clidata.us_heap.get(self.bytes(),offset)
+    }
+
+    /// literal of an ldstr operand, the token's row id is the #US offset
+    pub fn ldstr_string(&self,token:MetadataToken)->Result<UserString>{
+        if !token.is_user_string() {
+            return Err(Error::InvalidValue { what: "ldstr token", value: token.0 });
+        }
+        self.user_string(token.rid())
+    }
+
+    pub fn user_strings(&self)->Result<UserStringIter<'_>>{
+        self.clidata.us_heap.iter(self.bytes())
+    }
+
+    pub fn resources(&self)->Result<Vec<ResourceEntry>>{
+        parse_resources(&self.pe,self.bytes())
+    }
+
+    /// decode the first RT_VERSION resource, None if the image has none
+    pub fn version_info(&self)->Result<Option<VersionInfo>>{
+        match self.resources()?.iter().find(|r| r.is_type(RT_VERSION)) {
+            Some(res)=>Ok(Some(VersionInfo::parse(res.data)?)),
+            None=>Ok(None),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,10 @@
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str;
 
+use crate::data::Guid;
+use crate::error::*;
 use crate::tbl::*;
 use crate::util::*;
 use crate::reader::*;
@@ -12,100 +16,415 @@ pub struct CLIData {
     pub meta: CLIMetaData,
     pub tilde_stream: CLITildeStream,
 
-    pub addr_offset_code: usize,
-
     pub string_stream: CLIStringStream,
-    pub blob_base_addr: usize,
+    pub us_heap: UserStringHeap,
+    pub guid_heap: GuidHeap,
+    pub blob_heap: BlobHeap,
 
     pub tbl_module: CLITable<MetaModule>,
     pub tbl_typeref: CLITable<MetaTypeRef>,
     pub tbl_typedef: CLITable<MetaTypeDef>,
+    pub tbl_field: CLITable<MetaField>,
     pub tbl_methoddef: CLITable<MetaMethodDef>,
+    pub tbl_param: CLITable<MetaParam>,
+    pub tbl_interface_impl: CLITable<MetaInterfaceImpl>,
     pub tbl_member_ref: CLITable<MetaMemberRef>,
+    pub tbl_constant: CLITable<MetaConstant>,
     pub tbl_custom_attribute: CLITable<MetaCustomAttribute>,
+    pub tbl_field_marshal: CLITable<MetaFieldMarshal>,
+    pub tbl_decl_security: CLITable<MetaDeclSecurity>,
+    pub tbl_class_layout: CLITable<MetaClassLayout>,
+    pub tbl_field_layout: CLITable<MetaFieldLayout>,
     pub tbl_stand_alone_sig: CLITable<MetaStandAloneSig>,
+    pub tbl_event_map: CLITable<MetaEventMap>,
+    pub tbl_event: CLITable<MetaEvent>,
+    pub tbl_property_map: CLITable<MetaPropertyMap>,
+    pub tbl_property: CLITable<MetaProperty>,
+    pub tbl_method_semantics: CLITable<MetaMethodSemantics>,
+    pub tbl_method_impl: CLITable<MetaMethodImpl>,
+    pub tbl_module_ref: CLITable<MetaModuleRef>,
+    pub tbl_type_spec: CLITable<MetaTypeSpec>,
+    pub tbl_impl_map: CLITable<MetaImplMap>,
+    pub tbl_field_rva: CLITable<MetaFieldRVA>,
     pub tbl_assembly: CLITable<MetaAssembly>,
+    pub tbl_assembly_processor: CLITable<MetaAssemblyProcessor>,
+    pub tbl_assembly_os: CLITable<MetaAssemblyOS>,
     pub tbl_assembly_ref: CLITable<MetaAssemblyRef>,
+    pub tbl_assembly_ref_processor: CLITable<MetaAssemblyRefProcessor>,
+    pub tbl_assembly_ref_os: CLITable<MetaAssemblyRefOS>,
+    pub tbl_file: CLITable<MetaFile>,
+    pub tbl_exported_type: CLITable<MetaExportedType>,
+    pub tbl_manifest_resource: CLITable<MetaManifestResource>,
+    pub tbl_nested_class: CLITable<MetaNestedClass>,
+    pub tbl_generic_param: CLITable<MetaGenericParam>,
+    pub tbl_method_spec: CLITable<MetaMethodSpec>,
+    pub tbl_generic_param_constraint: CLITable<MetaGenericParamConstraint>,
+    pub tbl_field_ptr: CLITable<MetaFieldPtr>,
+    pub tbl_method_ptr: CLITable<MetaMethodPtr>,
+    pub tbl_param_ptr: CLITable<MetaParamPtr>,
+    pub tbl_event_ptr: CLITable<MetaEventPtr>,
+    pub tbl_property_ptr: CLITable<MetaPropertyPtr>,
+    pub tbl_enc_log: CLITable<MetaENCLog>,
+    pub tbl_enc_map: CLITable<MetaENCMap>,
 
 }
 
 impl CLIData {
-    pub fn parse_cli_data(reader: &mut BinaryReader, pe: &WinPe) -> CLIData {
+    pub fn parse_cli_data(reader: &mut BinaryReader, pe: &WinPe) -> Result<CLIData> {
         let mut clidata: CLIData = Default::default();
 
-        clidata.header = CLIHeader::parse(reader);
-        let meta = CLIMetaData::parse(reader);
-        clidata.tilde_stream = CLITildeStream::parse(reader);
+        clidata.header = CLIHeader::parse(reader)?;
+        let meta_pos = pe.rva_to_offset(clidata.header.metadata.rva)?;
+        reader.seek(meta_pos);
+        let meta = CLIMetaData::parse(reader)?;
+        let tables = meta.tables_stream().ok_or(Error::Unsupported("metadata without a #~ or #- stream"))?;
+        let tables_reader = &mut reader.sub_reader(meta.meta_pos + tables.offset as usize, tables.size as usize)?;
+        clidata.tilde_stream = CLITildeStream::parse(tables_reader)?;
 
 
         let meta_base_addr = meta.meta_pos;
 
         let (str_off, str_size) = meta.get_stream_rva(&"#Strings");
-        let str_start = meta_base_addr + str_off;
-        let str_end = str_start + str_size;
-        let string_stream = CLIStringStream::parse(reader, (str_start, str_end));
+        clidata.string_stream = CLIStringStream { base: meta_base_addr + str_off, size: str_size };
 
-        let (blob_off, blob_size) = meta.get_stream_rva(&"#Blob");
-        let blob_start = meta_base_addr + blob_off;
-        let _blob_end = blob_start + blob_size;
-        clidata.blob_base_addr = blob_start;
+        let (us_off, us_size) = meta.get_stream_rva(&"#US");
+        clidata.us_heap = UserStringHeap { base: meta_base_addr + us_off, size: us_size };
 
-        clidata.string_stream = string_stream;
-        clidata.addr_offset_code = (pe.base_of_code - 0x200) as usize;
+        let (guid_off, guid_size) = meta.get_stream_rva(&"#GUID");
+        clidata.guid_heap = GuidHeap::parse(reader, (meta_base_addr + guid_off, guid_size))?;
 
-        clidata.parse_tables(reader);
+        let (blob_off, blob_size) = meta.get_stream_rva(&"#Blob");
+        clidata.blob_heap = BlobHeap { base: meta_base_addr + blob_off, size: blob_size };
+
+        clidata.parse_tables(reader)?;
 
         clidata.meta = meta;
-        clidata
+        Ok(clidata)
     }
 
-    fn parse_tables(&mut self, reader: &mut BinaryReader) {
+    /// every table seeks to its own offset
+    fn parse_tables(&mut self, reader: &mut BinaryReader) -> Result<()> {
         let tilde_stream = &self.tilde_stream;
-        let string_stream = &self.string_stream;
-        self.tbl_module = MetaModule::parse_table(reader, tilde_stream, string_stream);
-        self.tbl_typeref = MetaTypeRef::parse_table(reader, tilde_stream, string_stream);
-        self.tbl_typedef = MetaTypeDef::parse_table(reader, tilde_stream, string_stream);
-        self.tbl_methoddef = MetaMethodDef::parse_table(reader, tilde_stream, string_stream);
-        self.tbl_member_ref = MetaMemberRef::parse_table(reader, tilde_stream, string_stream);
-        self.tbl_custom_attribute = MetaCustomAttribute::parse_table(reader, tilde_stream, string_stream);
-        self.tbl_stand_alone_sig = MetaStandAloneSig::parse_table(reader, tilde_stream, string_stream);
-        self.tbl_assembly = MetaAssembly::parse_table(reader, tilde_stream, string_stream);
-        self.tbl_assembly_ref = MetaAssemblyRef::parse_table(reader, tilde_stream, string_stream);
-//        println!("module end{:#x}",reader.pos);
-    }
-
-    #[inline]
-    pub fn get_rva_addr(&self, rva: usize) -> usize {
-        rva - self.addr_offset_code
-    }
-
-    #[inline]
-    pub fn parse_signature<T: Signature<T>>(&self, reader: &mut BinaryReader, blob_offset: usize) -> T {
-        let address = self.blob_base_addr + blob_offset;
-
-        //calculate byte length
-        reader.seek(address);
-        let leading_byte = reader.le_u8();
-
-
-        let mut _len: usize = 0;
-        if leading_byte >> 7 == 0 {
-            _len = (leading_byte & 0b01111111) as usize;
-        } else if leading_byte >> 6 == 0b10 {
-            let next_byte = reader.le_u8();
-            _len = ((leading_byte & 0b00111111) as usize) << 8 + next_byte as usize;
-        } else if leading_byte >> 5 == 0b110 {
-            _len = ((leading_byte & 0b00011111) as usize) << 24;
-            _len += (reader.le_u8() as usize) << 16;
-            _len += (reader.le_u8() as usize) << 8;
-            _len += reader.le_u8() as usize;
+        //heap bounds are checked once, names are decoded when asked for
+        self.string_stream.view(reader.raw_data)?;
+        self.tbl_module = MetaModule::parse_table(reader, tilde_stream)?;
+        self.tbl_typeref = MetaTypeRef::parse_table(reader, tilde_stream)?;
+        self.tbl_typedef = MetaTypeDef::parse_table(reader, tilde_stream)?;
+        self.tbl_field = MetaField::parse_table(reader, tilde_stream)?;
+        self.tbl_methoddef = MetaMethodDef::parse_table(reader, tilde_stream)?;
+        self.tbl_param = MetaParam::parse_table(reader, tilde_stream)?;
+        self.tbl_interface_impl = MetaInterfaceImpl::parse_table(reader, tilde_stream)?;
+        self.tbl_member_ref = MetaMemberRef::parse_table(reader, tilde_stream)?;
+        self.tbl_constant = MetaConstant::parse_table(reader, tilde_stream)?;
+        self.tbl_custom_attribute = MetaCustomAttribute::parse_table(reader, tilde_stream)?;
+        self.tbl_field_marshal = MetaFieldMarshal::parse_table(reader, tilde_stream)?;
+        self.tbl_decl_security = MetaDeclSecurity::parse_table(reader, tilde_stream)?;
+        self.tbl_class_layout = MetaClassLayout::parse_table(reader, tilde_stream)?;
+        self.tbl_field_layout = MetaFieldLayout::parse_table(reader, tilde_stream)?;
+        self.tbl_stand_alone_sig = MetaStandAloneSig::parse_table(reader, tilde_stream)?;
+        self.tbl_event_map = MetaEventMap::parse_table(reader, tilde_stream)?;
+        self.tbl_event = MetaEvent::parse_table(reader, tilde_stream)?;
+        self.tbl_property_map = MetaPropertyMap::parse_table(reader, tilde_stream)?;
+        self.tbl_property = MetaProperty::parse_table(reader, tilde_stream)?;
+        self.tbl_method_semantics = MetaMethodSemantics::parse_table(reader, tilde_stream)?;
+        self.tbl_method_impl = MetaMethodImpl::parse_table(reader, tilde_stream)?;
+        self.tbl_module_ref = MetaModuleRef::parse_table(reader, tilde_stream)?;
+        self.tbl_type_spec = MetaTypeSpec::parse_table(reader, tilde_stream)?;
+        self.tbl_impl_map = MetaImplMap::parse_table(reader, tilde_stream)?;
+        self.tbl_field_rva = MetaFieldRVA::parse_table(reader, tilde_stream)?;
+        self.tbl_assembly = MetaAssembly::parse_table(reader, tilde_stream)?;
+        self.tbl_assembly_processor = MetaAssemblyProcessor::parse_table(reader, tilde_stream)?;
+        self.tbl_assembly_os = MetaAssemblyOS::parse_table(reader, tilde_stream)?;
+        self.tbl_assembly_ref = MetaAssemblyRef::parse_table(reader, tilde_stream)?;
+        self.tbl_assembly_ref_processor = MetaAssemblyRefProcessor::parse_table(reader, tilde_stream)?;
+        self.tbl_assembly_ref_os = MetaAssemblyRefOS::parse_table(reader, tilde_stream)?;
+        self.tbl_file = MetaFile::parse_table(reader, tilde_stream)?;
+        self.tbl_exported_type = MetaExportedType::parse_table(reader, tilde_stream)?;
+        self.tbl_manifest_resource = MetaManifestResource::parse_table(reader, tilde_stream)?;
+        self.tbl_nested_class = MetaNestedClass::parse_table(reader, tilde_stream)?;
+        self.tbl_generic_param = MetaGenericParam::parse_table(reader, tilde_stream)?;
+        self.tbl_method_spec = MetaMethodSpec::parse_table(reader, tilde_stream)?;
+        self.tbl_generic_param_constraint = MetaGenericParamConstraint::parse_table(reader, tilde_stream)?;
+        self.tbl_field_ptr = MetaFieldPtr::parse_table(reader, tilde_stream)?;
+        self.tbl_method_ptr = MetaMethodPtr::parse_table(reader, tilde_stream)?;
+        self.tbl_param_ptr = MetaParamPtr::parse_table(reader, tilde_stream)?;
+        self.tbl_event_ptr = MetaEventPtr::parse_table(reader, tilde_stream)?;
+        self.tbl_property_ptr = MetaPropertyPtr::parse_table(reader, tilde_stream)?;
+        self.tbl_enc_log = MetaENCLog::parse_table(reader, tilde_stream)?;
+        self.tbl_enc_map = MetaENCMap::parse_table(reader, tilde_stream)?;
+        Ok(())
+    }
+
+    /// module version id, changes with every build
+    pub fn mvid(&self) -> Option<Guid> {
+        self.tbl_module.data.first().and_then(|m| self.guid_heap.get(m.mvid))
+    }
+
+    /// row the token points at, in any table this crate keeps
+    pub fn resolve(&self, token: MetadataToken) -> Result<TableRow<'_>> {
+        let table = match token.table() {
+            Some(t) => t,
+            None => return Err(Error::InvalidValue { what: "metadata token", value: token.0 }),
+        };
+        let rid = token.rid();
+        let row = match table {
+            CLITableId::Module => self.tbl_module.get_row(rid).map(TableRow::Module),
+            CLITableId::TypeRef => self.tbl_typeref.get_row(rid).map(TableRow::TypeRef),
+            CLITableId::TypeDef => self.tbl_typedef.get_row(rid).map(TableRow::TypeDef),
+            CLITableId::Field => self.tbl_field.get_row(rid).map(TableRow::Field),
+            CLITableId::MethodDef => self.tbl_methoddef.get_row(rid).map(TableRow::MethodDef),
+            CLITableId::Param => self.tbl_param.get_row(rid).map(TableRow::Param),
+            CLITableId::InterfaceImpl => self.tbl_interface_impl.get_row(rid).map(TableRow::InterfaceImpl),
+            CLITableId::MemberRef => self.tbl_member_ref.get_row(rid).map(TableRow::MemberRef),
+            CLITableId::Constant => self.tbl_constant.get_row(rid).map(TableRow::Constant),
+            CLITableId::CustomAttribute => self.tbl_custom_attribute.get_row(rid).map(TableRow::CustomAttribute),
+            CLITableId::FieldMarshal => self.tbl_field_marshal.get_row(rid).map(TableRow::FieldMarshal),
+            CLITableId::DeclSecurity => self.tbl_decl_security.get_row(rid).map(TableRow::DeclSecurity),
+            CLITableId::ClassLayout => self.tbl_class_layout.get_row(rid).map(TableRow::ClassLayout),
+            CLITableId::FieldLayout => self.tbl_field_layout.get_row(rid).map(TableRow::FieldLayout),
+            CLITableId::StandAloneSig => self.tbl_stand_alone_sig.get_row(rid).map(TableRow::StandAloneSig),
+            CLITableId::EventMap => self.tbl_event_map.get_row(rid).map(TableRow::EventMap),
+            CLITableId::Event => self.tbl_event.get_row(rid).map(TableRow::Event),
+            CLITableId::PropertyMap => self.tbl_property_map.get_row(rid).map(TableRow::PropertyMap),
+            CLITableId::Property => self.tbl_property.get_row(rid).map(TableRow::Property),
+            CLITableId::MethodSemantics => self.tbl_method_semantics.get_row(rid).map(TableRow::MethodSemantics),
+            CLITableId::MethodImpl => self.tbl_method_impl.get_row(rid).map(TableRow::MethodImpl),
+            CLITableId::ModuleRef => self.tbl_module_ref.get_row(rid).map(TableRow::ModuleRef),
+            CLITableId::TypeSpec => self.tbl_type_spec.get_row(rid).map(TableRow::TypeSpec),
+            CLITableId::ImplMap => self.tbl_impl_map.get_row(rid).map(TableRow::ImplMap),
+            CLITableId::FieldRVA => self.tbl_field_rva.get_row(rid).map(TableRow::FieldRVA),
+            CLITableId::Assembly => self.tbl_assembly.get_row(rid).map(TableRow::Assembly),
+            CLITableId::AssemblyProcessor => self.tbl_assembly_processor.get_row(rid).map(TableRow::AssemblyProcessor),
+            CLITableId::AssemblyOS => self.tbl_assembly_os.get_row(rid).map(TableRow::AssemblyOS),
+            CLITableId::AssemblyRef => self.tbl_assembly_ref.get_row(rid).map(TableRow::AssemblyRef),
+            CLITableId::AssemblyRefProcessor => self.tbl_assembly_ref_processor.get_row(rid).map(TableRow::AssemblyRefProcessor),
+            CLITableId::AssemblyRefOS => self.tbl_assembly_ref_os.get_row(rid).map(TableRow::AssemblyRefOS),
+            CLITableId::File => self.tbl_file.get_row(rid).map(TableRow::File),
+            CLITableId::ExportedType => self.tbl_exported_type.get_row(rid).map(TableRow::ExportedType),
+            CLITableId::ManifestResource => self.tbl_manifest_resource.get_row(rid).map(TableRow::ManifestResource),
+            CLITableId::NestedClass => self.tbl_nested_class.get_row(rid).map(TableRow::NestedClass),
+            CLITableId::GenericParam => self.tbl_generic_param.get_row(rid).map(TableRow::GenericParam),
+            CLITableId::MethodSpec => self.tbl_method_spec.get_row(rid).map(TableRow::MethodSpec),
+            CLITableId::GenericParamConstraint => self.tbl_generic_param_constraint.get_row(rid).map(TableRow::GenericParamConstraint),
+            CLITableId::Document | CLITableId::MethodDebugInformation | CLITableId::LocalScope | CLITableId::LocalVariable |
+            CLITableId::LocalConstant | CLITableId::ImportScope | CLITableId::StateMachineMethod |
+            CLITableId::CustomDebugInformation => return Err(Error::Unsupported("tokens into portable pdb tables")),
+            CLITableId::FieldPtr => self.tbl_field_ptr.get_row(rid).map(TableRow::FieldPtr),
+            CLITableId::MethodPtr => self.tbl_method_ptr.get_row(rid).map(TableRow::MethodPtr),
+            CLITableId::ParamPtr => self.tbl_param_ptr.get_row(rid).map(TableRow::ParamPtr),
+            CLITableId::EventPtr => self.tbl_event_ptr.get_row(rid).map(TableRow::EventPtr),
+            CLITableId::PropertyPtr => self.tbl_property_ptr.get_row(rid).map(TableRow::PropertyPtr),
+            CLITableId::ENCLog => self.tbl_enc_log.get_row(rid).map(TableRow::ENCLog),
+            CLITableId::ENCMap => self.tbl_enc_map.get_row(rid).map(TableRow::ENCMap),
+            CLITableId::Invalid => None,
+        };
+        row.ok_or(Error::InvalidValue { what: "metadata token", value: token.0 })
+    }
+
+    /// namespace qualified name of a TypeDef or TypeRef, None for a TypeSpec or a missing row
+    pub fn type_name(&self, data: &[u8], ty: TypeDefOrRef) -> Result<Option<String>> {
+        let row = match ty {
+            TypeDefOrRef::TypeDef(rid) => self.tbl_typedef.get_row(rid).map(|t| (t.namespace, t.name)),
+            TypeDefOrRef::TypeRef(rid) => self.tbl_typeref.get_row(rid).map(|t| (t.namespace, t.name)),
+            TypeDefOrRef::TypeSpec(_) => None,
+        };
+        let (namespace, name) = match row {
+            Some((namespace, name)) => (self.get_string(data, namespace)?, self.get_string(data, name)?),
+            None => return Ok(None),
+        };
+        if namespace.is_empty() {
+            Ok(Some(name.to_string()))
         } else {
-            panic!("invalid blob stream at addr: {}", &address);
+            Ok(Some(format!("{}.{}", namespace, name)))
+        }
+    }
+
+    /// Field rows of a TypeDef
+    pub fn type_fields(&self, typedef: u32) -> Vec<u32> {
+        let next = self.tbl_typedef.get_row(typedef + 1).map(|t| t.field_list);
+        match self.tbl_typedef.get_row(typedef) {
+            Some(t) => member_list(t.field_list, next, self.tbl_field.row, &self.tbl_field_ptr, |p| p.field),
+            None => Vec::new(),
+        }
+    }
+
+    /// MethodDef rows of a TypeDef
+    pub fn type_methods(&self, typedef: u32) -> Vec<u32> {
+        let next = self.tbl_typedef.get_row(typedef + 1).map(|t| t.method_list);
+        match self.tbl_typedef.get_row(typedef) {
+            Some(t) => member_list(t.method_list, next, self.tbl_methoddef.row, &self.tbl_method_ptr, |p| p.method),
+            None => Vec::new(),
+        }
+    }
+
+    /// Param rows of a MethodDef
+    pub fn method_params(&self, method: u32) -> Vec<u32> {
+        let next = self.tbl_methoddef.get_row(method + 1).map(|m| m.param_list);
+        match self.tbl_methoddef.get_row(method) {
+            Some(m) => member_list(m.param_list, next, self.tbl_param.row, &self.tbl_param_ptr, |p| p.param),
+            None => Vec::new(),
+        }
+    }
+
+    /// Event rows of a TypeDef, found through its EventMap row
+    pub fn type_events(&self, typedef: u32) -> Vec<u32> {
+        let map = match self.tbl_event_map.data.iter().position(|m| m.parent == typedef) {
+            Some(index) => index as u32 + 1,
+            None => return Vec::new(),
+        };
+        let next = self.tbl_event_map.get_row(map + 1).map(|m| m.event_list);
+        let start = self.tbl_event_map.get_row(map).map_or(0, |m| m.event_list);
+        member_list(start, next, self.tbl_event.row, &self.tbl_event_ptr, |p| p.event)
+    }
+
+    /// Property rows of a TypeDef, found through its PropertyMap row
+    pub fn type_properties(&self, typedef: u32) -> Vec<u32> {
+        let map = match self.tbl_property_map.data.iter().position(|m| m.parent == typedef) {
+            Some(index) => index as u32 + 1,
+            None => return Vec::new(),
+        };
+        let next = self.tbl_property_map.get_row(map + 1).map(|m| m.property_list);
+        let start = self.tbl_property_map.get_row(map).map_or(0, |m| m.property_list);
+        member_list(start, next, self.tbl_property.row, &self.tbl_property_ptr, |p| p.property)
+    }
+
+    /// string at `offset` of the #Strings heap, `data` is the whole image
+    pub fn get_string<'a>(&self, data: &'a [u8], offset: u32) -> Result<&'a str> {
+        self.string_stream.view(data)?.get(offset)
+    }
+
+    /// bytes of the blob at `index` of the #Blob heap, `data` is the whole image
+    pub fn get_blob<'a>(&self, data: &'a [u8], index: u32) -> Result<&'a [u8]> {
+        self.blob_heap.get(data, index)
+    }
+
+    /// signature stored in the #Blob heap at `blob_offset`, parsed from the blob's bytes only
+    pub fn parse_signature<T: Signature<T>>(&self, data: &[u8], blob_offset: u32) -> Result<T> {
+        let blob = self.blob_heap.get(data, blob_offset)?;
+        T::parse_signature(&mut BinaryReader::new(blob), blob.len())
+    }
+
+    /// signature of a MemberRef row
+    pub fn member_ref_sig(&self, data: &[u8], rid: u32) -> Result<MemberRefSig> {
+        let member = self.tbl_member_ref.get_row(rid).ok_or(Error::InvalidValue { what: "MemberRef row", value: rid })?;
+        self.parse_signature(data, member.signature)
+    }
+
+    /// MethodDef of `typedef` named `name` that a call through `call_site` binds to
+    pub fn find_method(&self, data: &[u8], typedef: u32, name: &str, call_site: &MethodDefSig) -> Result<Option<u32>> {
+        for rid in self.type_methods(typedef) {
+            let method = self.tbl_methoddef.get_row(rid).ok_or(Error::InvalidValue { what: "method row", value: rid })?;
+            //a name that does not decode cannot be the one asked for
+            if self.get_string(data, method.name).map_or(true, |n| n != name) {
+                continue;
+            }
+            let sig: MethodDefSig = self.parse_signature(data, method.signature)?;
+            if sig.matches_call_site(call_site) {
+                return Ok(Some(rid));
+            }
         }
+        Ok(None)
+    }
+
+    /// MethodDef a method MemberRef points at when it is defined in this module, None for
+    /// members of TypeRef, ModuleRef and TypeSpec parents which live elsewhere
+    pub fn resolve_method_ref(&self, data: &[u8], rid: u32) -> Result<Option<u32>> {
+        let member = self.tbl_member_ref.get_row(rid).ok_or(Error::InvalidValue { what: "MemberRef row", value: rid })?;
+        let call_site = match self.member_ref_sig(data, rid)? {
+            MemberRefSig::Method(sig) => sig,
+            MemberRefSig::Field(_) => return Err(Error::InvalidValue { what: "method MemberRef", value: rid }),
+        };
+        match member.parent() {
+            //vararg call sites reference the MethodDef they call directly
+            Some(MemberRefParent::MethodDef(method)) => Ok(Some(method)),
+            Some(MemberRefParent::TypeDef(typedef)) => self.find_method(data, typedef, self.get_string(data, member.name)?, &call_site),
+            Some(_) => Ok(None),
+            None => Err(Error::InvalidValue { what: "MemberRefParent", value: member.class }),
+        }
+    }
 
-        assert_ne!(_len, 0_usize);
-        T::parse_signature(reader, _len)
+    /// type a TypeSpec row stands for, e.g. a GenericInst of List`1 for List<int>
+    pub fn type_spec(&self, data: &[u8], rid: u32) -> Result<TypeSig> {
+        let spec = self.tbl_type_spec.get_row(rid).ok_or(Error::InvalidValue { what: "TypeSpec row", value: rid })?;
+        self.parse_signature(data, spec.signature)
     }
+
+    /// method and type arguments of a MethodSpec row
+    pub fn method_spec(&self, data: &[u8], rid: u32) -> Result<MethodInstance> {
+        let spec = self.tbl_method_spec.get_row(rid).ok_or(Error::InvalidValue { what: "MethodSpec row", value: rid })?;
+        let method = spec.method_def_or_ref().ok_or(Error::InvalidValue { what: "MethodDefOrRef", value: spec.method })?;
+        let sig: MethodSpecSig = self.parse_signature(data, spec.instantiation)?;
+        let type_args = match method {
+            MethodDefOrRef::MemberRef(member) => self.member_ref_type_args(data, member)?,
+            MethodDefOrRef::MethodDef(_) => Vec::new(),
+        };
+        Ok(MethodInstance {
+            method,
+            type_args,
+            method_args: sig.args,
+        })
+    }
+
+    /// arguments of the generic type instance a MemberRef belongs to, e.g. int for
+    /// List<int>.Add. empty unless the parent is a GenericInst TypeSpec
+    pub fn member_ref_type_args(&self, data: &[u8], rid: u32) -> Result<Vec<TypeSig>> {
+        let member = self.tbl_member_ref.get_row(rid).ok_or(Error::InvalidValue { what: "MemberRef row", value: rid })?;
+        match member.parent() {
+            Some(MemberRefParent::TypeSpec(spec)) => {
+                let sig = self.type_spec(data, spec)?;
+                Ok(sig.generic_instance().map_or(Vec::new(), |(_, args)| args.to_vec()))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// locals of a method body, `token` is the header's LocalVarSigTok. None for the nil token
+    pub fn local_var_sig(&self, data: &[u8], token: MetadataToken) -> Result<Option<LocalVarSig>> {
+        if token.is_nil() {
+            return Ok(None);
+        }
+        if token.table() != Some(CLITableId::StandAloneSig) {
+            return Err(Error::InvalidValue { what: "local signature token", value: token.0 });
+        }
+        let sig = self.tbl_stand_alone_sig.get_row(token.rid()).ok_or(Error::InvalidValue { what: "StandAloneSig row", value: token.rid() })?;
+        Ok(Some(self.parse_signature(data, sig.signature)?))
+    }
+}
+
+/// rows from `start` up to the next owner's list, or the end of the table for the last owner.
+/// with a Ptr table the list indexes the Ptr table, which names the actual rows
+fn member_list<P: MetaItem<P>>(start: u32, next: Option<u32>, rows: u32, ptr: &CLITable<P>, target: fn(&P) -> u32) -> Vec<u32> {
+    let count = if ptr.data.is_empty() { rows } else { ptr.data.len() as u32 };
+    let end = next.unwrap_or(count + 1).min(count + 1);
+    (start.max(1)..end).map(|i| ptr.get_row(i).map_or(i, target)).collect()
+}
+
+/// COMIMAGE_FLAGS_* of the CLI header
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ComImageFlags(pub u32);
+
+impl ComImageFlags {
+    pub const ILONLY: ComImageFlags = ComImageFlags(0x0000_0001);
+    pub const REQUIRED_32BIT: ComImageFlags = ComImageFlags(0x0000_0002);
+    pub const STRONGNAMESIGNED: ComImageFlags = ComImageFlags(0x0000_0008);
+    pub const NATIVE_ENTRYPOINT: ComImageFlags = ComImageFlags(0x0000_0010);
+    pub const TRACKDEBUGDATA: ComImageFlags = ComImageFlags(0x0001_0000);
+    /// only meaningful together with REQUIRED_32BIT
+    pub const PREFERRED_32BIT: ComImageFlags = ComImageFlags(0x0002_0000);
+
+    pub fn contains(&self, other: ComImageFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// what `entry_point_token` refers to, decided by COMIMAGE_FLAGS_NATIVE_ENTRYPOINT
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryPoint {
+    None,
+    /// MethodDef or File token
+    Token(MetadataToken),
+    /// rva of native code, never interpreted
+    Native(u32),
 }
 
 #[derive(Debug, Default)]
@@ -113,9 +432,15 @@ pub struct CLIHeader {
     pub major_runtime_ver: u16,
     pub minor_runtime_ver: u16,
     pub metadata: DataPointer,
-    pub flags: u32,
-    pub entry_point_token: u32,
+    pub flags: ComImageFlags,
+    pub entry_point_token: MetadataToken,
+    pub resources: DataPointer,
     pub strong_name_signature: DataPointer,
+    pub code_manager_tbl: DataPointer,
+    pub vtable_fixups: DataPointer,
+    pub export_addr_tbl_jumps: DataPointer,
+    /// READYTORUN_HEADER of ReadyToRun images, zero for pure IL
+    pub managed_native_header: DataPointer,
 }
 
 impl CLIHeader {
@@ -124,28 +449,69 @@ impl CLIHeader {
             major_runtime_ver: 0,
             minor_runtime_ver: 0,
             metadata: Default::default(),
-            flags: 0,
-            entry_point_token: 0,
+            flags: Default::default(),
+            entry_point_token: Default::default(),
+            resources: Default::default(),
             strong_name_signature: Default::default(),
+            code_manager_tbl: Default::default(),
+            vtable_fixups: Default::default(),
+            export_addr_tbl_jumps: Default::default(),
+            managed_native_header: Default::default(),
         }
     }
 
-    pub fn parse(reader: &mut BinaryReader) -> CLIHeader {
+    pub fn parse(reader: &mut BinaryReader) -> Result<CLIHeader> {
         let mut header = CLIHeader::default();
 
         reader.tag(&[0x48, 0, 0, 0]);
-        header.major_runtime_ver = reader.le_u16();
-        header.minor_runtime_ver = reader.le_u16();
-        header.metadata = reader.data_pointer();
-        header.flags = reader.le_u32();
-        header.entry_point_token = reader.le_u32();
-        let _resources = reader.data_pointer();
-        header.strong_name_signature = reader.data_pointer();
-        let _code_manager_tbl = reader.ate(8);
-        let _vtable_fixups = reader.data_pointer();
-        let _export_addr_tbl_jumps = reader.ate(8);
-        let _managed_native_header = reader.ate(8);
-        header
+        header.major_runtime_ver = reader.le_u16()?;
+        header.minor_runtime_ver = reader.le_u16()?;
+        header.metadata = reader.data_pointer()?;
+        header.flags = ComImageFlags(reader.le_u32()?);
+        header.entry_point_token = MetadataToken(reader.le_u32()?);
+        header.resources = reader.data_pointer()?;
+        header.strong_name_signature = reader.data_pointer()?;
+        header.code_manager_tbl = reader.data_pointer()?;
+        header.vtable_fixups = reader.data_pointer()?;
+        header.export_addr_tbl_jumps = reader.data_pointer()?;
+        header.managed_native_header = reader.data_pointer()?;
+        Ok(header)
+    }
+
+    pub fn is_il_only(&self) -> bool {
+        self.flags.contains(ComImageFlags::ILONLY)
+    }
+
+    /// image carries native code besides IL, e.g. C++/CLI output
+    pub fn is_mixed_mode(&self) -> bool {
+        !self.is_il_only()
+    }
+
+    /// ReadyToRun (or legacy NGen) header present
+    pub fn has_managed_native_header(&self) -> bool {
+        self.managed_native_header.rva != 0 && self.managed_native_header.size != 0
+    }
+
+    pub fn requires_32bit(&self) -> bool {
+        self.flags.contains(ComImageFlags::REQUIRED_32BIT) && !self.flags.contains(ComImageFlags::PREFERRED_32BIT)
+    }
+
+    pub fn prefers_32bit(&self) -> bool {
+        self.flags.contains(ComImageFlags::REQUIRED_32BIT) && self.flags.contains(ComImageFlags::PREFERRED_32BIT)
+    }
+
+    pub fn is_strong_name_signed(&self) -> bool {
+        self.flags.contains(ComImageFlags::STRONGNAMESIGNED)
+    }
+
+    pub fn entry_point(&self) -> EntryPoint {
+        if self.entry_point_token.0 == 0 {
+            EntryPoint::None
+        } else if self.flags.contains(ComImageFlags::NATIVE_ENTRYPOINT) {
+            EntryPoint::Native(self.entry_point_token.0)
+        } else {
+            EntryPoint::Token(self.entry_point_token)
+        }
     }
 }
 
@@ -171,23 +537,27 @@ impl CLIMetaData {
         }
     }
 
-    pub fn parse(reader: &mut BinaryReader) -> CLIMetaData {
+    pub fn parse(reader: &mut BinaryReader) -> Result<CLIMetaData> {
         let mut metadata: CLIMetaData = Default::default();
 
-        reader.ate_till_tag(&[0x42, 0x53, 0x4A, 0x42]);
         metadata.meta_pos = reader.pos;
-        reader.ate(4);
-
-        metadata.major_version = reader.le_u16();
-        metadata.minor_version = reader.le_u16();
-        reader.ate(4);
-        let ver_sz = reader.le_u32();
-        metadata.cli_ve_str = reader.str(ver_sz as usize);
-        reader.ate(2);
-        metadata.num_of_stream = reader.le_u16();
+        reader.expect_tag(&[0x42, 0x53, 0x4A, 0x42])?;
+
+        metadata.major_version = reader.le_u16()?;
+        metadata.minor_version = reader.le_u16()?;
+        reader.ate(4)?;
+        let ver_sz = reader.le_u32()?;
+        metadata.cli_ve_str = reader.str(ver_sz as usize)?;
+        reader.ate(2)?;
+        metadata.num_of_stream = reader.le_u16()?;
+
+        metadata.stream_header = reader.repeat(CLIStreamHeader::parse, metadata.num_of_stream as u32)?;
+        Ok(metadata)
+    }
 
-        metadata.stream_header = reader.repeat(CLIStreamHeader::parse, metadata.num_of_stream as u32);
-        metadata
+    /// #~, or the uncompressed #- written by edit and continue and some obfuscators
+    pub fn tables_stream(&self) -> Option<&CLIStreamHeader> {
+        self.stream_header.iter().find(|s| s.name == "#~" || s.name == "#-")
     }
 
     pub fn get_stream_rva(&self, name: &str) -> (usize, usize) {
@@ -211,18 +581,24 @@ pub struct CLIStreamHeader {
 }
 
 impl CLIStreamHeader {
-    pub fn parse(reader: &mut BinaryReader) -> CLIStreamHeader {
-        let offset = reader.le_u32();
-        let size = reader.le_u32();
-        let name = reader.str_pad();
-        CLIStreamHeader {
+    pub fn parse(reader: &mut BinaryReader) -> Result<CLIStreamHeader> {
+        let offset = reader.le_u32()?;
+        let size = reader.le_u32()?;
+        let name = reader.str_pad()?;
+        Ok(CLIStreamHeader {
             offset: offset,
             size: size,
             name: name,
-        }
+        })
     }
 }
 
+pub const HEAP_STRING_4: u8 = 0x01;
+pub const HEAP_GUID_4: u8 = 0x02;
+pub const HEAP_BLOB_4: u8 = 0x04;
+/// a u32 of extra data follows the row counts
+pub const HEAP_EXTRA_DATA: u8 = 0x40;
+
 #[derive(Debug, Copy, Clone, Default)]
 pub struct CLIHeapSize {
     pub string: u8,
@@ -233,9 +609,9 @@ pub struct CLIHeapSize {
 impl CLIHeapSize {
     pub fn new(heapsize: u8) -> CLIHeapSize {
         CLIHeapSize {
-            string: if heapsize & 0b1 > 0 { 4 } else { 2 },
-            guid: if heapsize & 0b10 > 0 { 4 } else { 2 },
-            blob: if heapsize & 0b100 > 0 { 4 } else { 2 },
+            string: if heapsize & HEAP_STRING_4 > 0 { 4 } else { 2 },
+            guid: if heapsize & HEAP_GUID_4 > 0 { 4 } else { 2 },
+            blob: if heapsize & HEAP_BLOB_4 > 0 { 4 } else { 2 },
         }
     }
 }
@@ -244,77 +620,183 @@ impl CLIHeapSize {
 pub struct CLITildeStream {
     pub major_ver: u8,
     pub minor_ver: u8,
+    /// raw HeapSizes byte
+    pub heap_flags: u8,
     pub heap_size: CLIHeapSize,
     pub valid: u64,
     pub sorted: u64,
     pub rows: Vec<u32>,
+    /// present when HEAP_EXTRA_DATA is set
+    pub extra_data: Option<u32>,
 
     pub column_size: HashMap<CLIColumnType, u8>,
     pub table_rows: Vec<u32>,
     pub table_valid: Vec<CLITableId>,
+    /// byte size of one row, indexed by table id
+    pub row_size: Vec<usize>,
+    /// absolute position of the first row of each table, indexed by table id
+    pub table_pos: Vec<usize>,
+    /// row counts of the assembly a portable pdb describes, indexed by table id, empty otherwise
+    pub type_system_rows: Vec<u32>,
 
 }
 
+/// one line of the table layout dump
+#[derive(Debug)]
+pub struct TableLayout {
+    pub table: CLITableId,
+    pub rows: u32,
+    pub row_size: usize,
+    pub offset: usize,
+    pub columns: Vec<(CLIColumn, u8)>,
+}
+
+impl fmt::Display for TableLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02X} {:<24} rows {:>8} size {:>3} at {:#010x} |", self.table as u8, format!("{:?}", self.table),
+               self.rows, self.row_size, self.offset)?;
+        for (column, width) in &self.columns {
+            write!(f, " {:?}:{}", column, width)?;
+        }
+        Ok(())
+    }
+}
+
 impl CLITildeStream {
-    pub fn parse(reader: &mut BinaryReader) -> CLITildeStream {
+    pub fn parse(reader: &mut BinaryReader) -> Result<CLITildeStream> {
+        CLITildeStream::parse_with_type_system(reader, Vec::new())
+    }
+
+    /// tilde stream of a portable pdb, whose index columns are sized by the row counts
+    /// of type system tables that live in the assembly rather than the pdb
+    pub fn parse_with_type_system(reader: &mut BinaryReader, type_system_rows: Vec<u32>) -> Result<CLITildeStream> {
         let mut tilde: CLITildeStream = Default::default();
+        tilde.type_system_rows = type_system_rows;
 
-        reader.ate(4);
-        tilde.major_ver = reader.le_u8();
-        tilde.minor_ver = reader.le_u8();
+        reader.ate(4)?;
+        tilde.major_ver = reader.le_u8()?;
+        tilde.minor_ver = reader.le_u8()?;
 
-        let raw_heap_size = reader.le_u8();
-        tilde.heap_size = CLIHeapSize::new(raw_heap_size);
+        tilde.heap_flags = reader.le_u8()?;
+        tilde.heap_size = CLIHeapSize::new(tilde.heap_flags);
 
-        reader.tag(&[0x01]);
-        tilde.valid = reader.le_u64();
-        tilde.sorted = reader.le_u64();
+        //reserved, always 1
+        reader.ate(1)?;
+        tilde.valid = reader.le_u64()?;
+        tilde.sorted = reader.le_u64()?;
 
         let table_count = BitUtility::bits_count_u64(tilde.valid) as u32;
-        tilde.rows = reader.repeat(BinaryReader::le_u32, table_count);
+        tilde.rows = reader.repeat(BinaryReader::le_u32, table_count)?;
+        if tilde.heap_flags & HEAP_EXTRA_DATA != 0 {
+            tilde.extra_data = Some(reader.le_u32()?);
+        }
         tilde.calculate_table_data();
+        //the reader covers only the stream, positions are kept relative to the whole image
+        tilde.calculate_table_pos(reader.abs_pos(), reader.base + reader.raw_data.len())?;
 
-        tilde
+        Ok(tilde)
     }
 
     fn calculate_table_data(&mut self) {
-        let _table_count = self.rows.len();
+        //row counts follow the valid bits, including tables this crate does not know
         let mut table_rows: Vec<u32> = vec![0; 64];
-        let rows = &self.rows;
-        let mut table_map = CLITableId::map();
-        table_map.sort();
-        let valid = self.valid;
-        let mut index: usize = 0;
-        for &tableid in table_map.iter() {
-            if valid & (1 << tableid as u8) > 0 {
-                self.table_valid.push(tableid);
-                table_rows[tableid as usize] = rows[index];
-                index += 1;
+        let mut rows = self.rows.iter();
+        for id in 0..64 {
+            if self.valid & (1 << id) != 0 {
+                table_rows[id] = *rows.next().unwrap_or(&0);
+                if let Some(table) = CLITableId::from_id(id as u8) {
+                    self.table_valid.push(table);
+                }
             }
         }
+        self.table_rows = table_rows;
 
-        //column rows
         let mut column_size: HashMap<CLIColumnType, u8> = HashMap::new();
-        let column_map = &CLICOLUMN_MAP;
-        for (&column, table_vec) in column_map.iter() {
-            let bit_count = (table_vec.len() as f32).log2().ceil() as u8;
-            let mut tbl_max_row = 0_u32;
-            for &tblid in table_vec.iter() {
-                if tblid != CLITableId::Invalid {
-                    tbl_max_row = tbl_max_row.max(table_rows[tblid as usize])
-                }
-            }
+        for &column in CLICOLUMN_MAP.keys() {
+            let max_rows = column.tables().iter()
+                .filter(|&&t| t != CLITableId::Invalid)
+                .map(|&t| self.index_rows(t))
+                .max()
+                .unwrap_or(0);
+            //the row has to fit next to the tag in 16 bits
+            let byte_size = if max_rows < 1 << (16 - column.tag_bits()) { 2 } else { 4 };
+            column_size.insert(column, byte_size);
+        }
+        self.column_size = column_size;
+    }
 
-            let byte_size: u8 = if tbl_max_row > (1 << (16 - bit_count)) {
-                4
-            } else {
-                2
+    /// tables are stored back to back in table id order and have to end before `stream_end`
+    fn calculate_table_pos(&mut self, tables_start: usize, stream_end: usize) -> Result<()> {
+        let mut row_size = vec![0; 64];
+        let mut table_pos = vec![tables_start; 64];
+        let mut pos = tables_start;
+        for id in 0..64 {
+            table_pos[id] = pos;
+            if self.table_rows[id] == 0 {
+                continue;
+            }
+            let table = match CLITableId::from_id(id as u8) {
+                Some(t) => t,
+                None => return Err(Error::InvalidValue { what: "metadata table id", value: id as u32 }),
             };
-            column_size.insert(column, byte_size);
+            row_size[id] = table.columns().iter().map(|&c| self.get_column_width(c) as usize).sum();
+            pos = row_size[id].checked_mul(self.table_rows[id] as usize)
+                .and_then(|size| pos.checked_add(size))
+                .filter(|&end| end <= stream_end)
+                .ok_or(Error::Truncated { offset: pos })?;
         }
+        self.row_size = row_size;
+        self.table_pos = table_pos;
+        Ok(())
+    }
 
-        self.table_rows = table_rows;
-        self.column_size = column_size;
+    /// byte width of a column, the single place heap, simple and coded index sizes are decided
+    pub fn get_column_width(&self, column: CLIColumn) -> u8 {
+        match column {
+            CLIColumn::U16 => 2,
+            CLIColumn::U32 => 4,
+            CLIColumn::Str => self.heap_size.string,
+            CLIColumn::Guid => self.heap_size.guid,
+            CLIColumn::Blob => self.heap_size.blob,
+            CLIColumn::Index(table) => if self.index_rows(table) < 0x10000 { 2 } else { 4 },
+            CLIColumn::Coded(column) => self.get_column_byte(column),
+        }
+    }
+
+    /// layout of every present table, for cross checking against other metadata tools
+    pub fn layout(&self) -> Vec<TableLayout> {
+        self.table_valid.iter().map(|&table| TableLayout {
+            table,
+            rows: self.get_table_row(table),
+            row_size: self.get_row_size(table),
+            offset: self.get_table_pos(table),
+            columns: table.columns().iter().map(|&c| (c, self.get_column_width(c))).collect(),
+        }).collect()
+    }
+
+    pub fn get_row_size(&self, table_id: CLITableId) -> usize {
+        self.row_size[table_id as usize]
+    }
+
+    pub fn get_table_pos(&self, table_id: CLITableId) -> usize {
+        self.table_pos[table_id as usize]
+    }
+
+    /// read every column of the row at the reader's position
+    pub fn read_row(&self, reader: &mut BinaryReader, table_id: CLITableId) -> Result<Row> {
+        let mut row: Row = [0; MAX_COLUMNS];
+        for (value, &column) in row.iter_mut().zip(table_id.columns()) {
+            *value = reader.le_uint(self.get_column_width(column))?;
+        }
+        Ok(row)
+    }
+
+    /// rows an index into `table` has to address
+    fn index_rows(&self, table: CLITableId) -> u32 {
+        match self.type_system_rows.get(table as usize) {
+            Some(&rows) if table < CLITableId::Document => rows,
+            _ => self.get_table_row(table),
+        }
     }
 
     pub fn get_table_row(self: &Self, table_id: CLITableId) -> u32 {
@@ -326,63 +808,176 @@ impl CLITildeStream {
     }
 }
 
+/// location of the #Strings heap, strings are read on demand through a [`StringHeap`] view
 #[derive(Debug, Default)]
 pub struct CLIStringStream {
-    pub data: Vec<Rc<String>>,
-    pub index_map: HashMap<u32, u32>,
+    pub base: usize,
+    pub size: usize,
 }
 
 impl CLIStringStream {
-    pub fn parse(reader: &mut BinaryReader, stream_info: (usize, usize)) -> CLIStringStream {
-        let max_addr = stream_info.1;
-        let start_addr = stream_info.0;
+    pub fn view<'a>(&self, data: &'a [u8]) -> Result<StringHeap<'a>> {
+        let heap = BinaryReader::new(data).sub_reader(self.base, self.size)?;
+        Ok(StringHeap { base: self.base, data: heap.raw_data })
+    }
+}
+
+/// #Strings heap borrowed from the image. columns may point anywhere into the heap,
+/// including into the tail of another string, as compilers merge common suffixes
+#[derive(Debug, Copy, Clone)]
+pub struct StringHeap<'a> {
+    base: usize,
+    data: &'a [u8],
+}
+
+impl<'a> StringHeap<'a> {
+    /// NUL terminated string starting at heap offset `offset`
+    pub fn get(&self, offset: u32) -> Result<&'a str> {
+        let start = offset as usize;
+        //an empty heap still answers the null index
+        if start == 0 && self.data.is_empty() {
+            return Ok("");
+        }
+        if start >= self.data.len() {
+            return Err(Error::Truncated { offset: self.base + start });
+        }
+        let bytes = &self.data[start..];
+        let len = bytes.iter().position(|&b| b == 0).ok_or(Error::Truncated { offset: self.base + self.data.len() })?;
+        str::from_utf8(&bytes[..len]).map_err(|_| Error::InvalidUtf8 { offset: self.base + start })
+    }
+}
 
-        let prev_pos = reader.pos;
-        reader.seek(start_addr + 1);
+/// #GUID heap, 16 byte entries addressed by 1-based index
+#[derive(Debug, Default)]
+pub struct GuidHeap {
+    pub guids: Vec<Guid>,
+}
 
-        let mut data: Vec<Rc<String>> = Vec::new();
-        let mut index_map = HashMap::new();
+impl GuidHeap {
+    pub fn parse(reader: &mut BinaryReader, stream_info: (usize, usize)) -> Result<GuidHeap> {
+        let (start, size) = stream_info;
+        let heap = &mut reader.sub_reader(start, size)?;
+        let guids = heap.repeat(BinaryReader::guid, (size / 16) as u32)?;
+        Ok(GuidHeap { guids })
+    }
+
+    /// None for the null index 0 or an index past the heap
+    pub fn get(&self, index: u32) -> Option<Guid> {
+        if index == 0 {
+            return None;
+        }
+        self.guids.get(index as usize - 1).copied()
+    }
+}
 
-        let str_empty = Rc::new(String::from(""));
-        data.push(str_empty);
-        index_map.insert(0, 0);
+/// literal of the #US heap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserString {
+    Text(String),
+    /// not valid UTF-16, e.g. a lone surrogate
+    Utf16(Vec<u16>),
+}
 
-        let mut str_count = 1;
-        let mut str_pos: u32 = 1;
-        while reader.pos < max_addr {
-            let str = reader.str_read();
-            if str.is_none() {
-                break;
-            } else {
-                index_map.insert(str_pos, str_count);
-                str_pos = (reader.pos - start_addr) as u32;
-                data.push(Rc::new(str.unwrap()));
-                str_count += 1;
-            }
+impl UserString {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            UserString::Text(s) => Some(s),
+            UserString::Utf16(_) => None,
         }
-        reader.seek(prev_pos);
+    }
 
-        CLIStringStream {
-            data,
-            index_map,
+    pub fn to_string_lossy(&self) -> String {
+        match self {
+            UserString::Text(s) => s.clone(),
+            UserString::Utf16(units) => String::from_utf16_lossy(units),
         }
     }
+}
 
-    pub fn get_str_by_index(&self, ind: u32) -> Rc<String> {
-        let index = self.index_map.get(&ind);
-        let val = if index.is_none() {
-            Rc::clone(&self.data[0])
-        } else {
-            let index = index.unwrap();
-            Rc::clone(&self.data[*index as usize])
-        };
-        val
+/// location of the #Blob heap, lookups read the image bytes they are given
+#[derive(Debug, Default, Clone)]
+pub struct BlobHeap {
+    pub base: usize,
+    pub size: usize,
+}
+
+impl BlobHeap {
+    /// blob at `offset`, without its compressed length prefix
+    pub fn get<'a>(&self, data: &'a [u8], offset: u32) -> Result<&'a [u8]> {
+        let heap = &mut BinaryReader::new(data).sub_reader(self.base, self.size)?;
+        heap.seek(offset as usize);
+        let len = heap.compressed_u32()?;
+        Ok(heap.bytes(len as usize)?)
     }
 }
 
+/// location of the #US heap, lookups read the image bytes they are given
+#[derive(Debug, Default, Clone)]
+pub struct UserStringHeap {
+    pub base: usize,
+    pub size: usize,
+}
+
+impl UserStringHeap {
+    /// literal at `offset`, the rid of an ldstr token
+    pub fn get(&self, data: &[u8], offset: u32) -> Result<UserString> {
+        let heap = &mut BinaryReader::new(data).sub_reader(self.base, self.size)?;
+        heap.seek(offset as usize);
+        read_user_string(heap)
+    }
+
+    /// every literal with its offset, in heap order
+    pub fn iter<'a>(&self, data: &'a [u8]) -> Result<UserStringIter<'a>> {
+        let mut heap = BinaryReader::new(data).sub_reader(self.base, self.size)?;
+        //offset 0 is the empty string
+        heap.seek(1.min(self.size));
+        Ok(UserStringIter { heap })
+    }
+}
+
+/// length in bytes including the trailing flag byte, then UTF-16 LE
+fn read_user_string(heap: &mut BinaryReader) -> Result<UserString> {
+    let len = heap.compressed_u32()? as usize;
+    if len == 0 {
+        return Ok(UserString::Text(String::new()));
+    }
+    if len % 2 == 0 {
+        return Err(Error::InvalidValue { what: "user string length", value: len as u32 });
+    }
+    let bytes = heap.bytes(len)?;
+    let units: Vec<u16> = bytes[..len - 1].chunks(2).map(|c| c[0] as u16 | (c[1] as u16) << 8).collect();
+    match String::from_utf16(&units) {
+        Ok(s) => Ok(UserString::Text(s)),
+        Err(_) => Ok(UserString::Utf16(units)),
+    }
+}
+
+pub struct UserStringIter<'a> {
+    heap: BinaryReader<'a>,
+}
+
+impl<'a> Iterator for UserStringIter<'a> {
+    type Item = Result<(u32, UserString)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        //the heap is padded with zero bytes, which read as empty entries
+        while self.heap.remaining() > 0 && self.heap.raw_data[self.heap.pos] == 0 {
+            self.heap.pos += 1;
+        }
+        if self.heap.remaining() == 0 {
+            return None;
+        }
+        let offset = self.heap.pos as u32;
+        let item = read_user_string(&mut self.heap).map(|s| (offset, s));
+        if item.is_err() {
+            self.heap.seek(self.heap.raw_data.len());
+        }
+        Some(item)
+    }
+}
 
 pub trait Signature<T> {
-    fn parse_signature(reader: &mut BinaryReader, length: usize) -> T;
+    fn parse_signature(reader: &mut BinaryReader, length: usize) -> Result<T>;
 }
 
 
@@ -406,87 +1001,411 @@ pub enum MethodImplFlags {
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RetType {
-    pub custom_mod: bool,
+    pub custom_mods: Vec<CustomMod>,
     pub by_ref: bool,
-    pub typed: ElementType,
+    pub type_sig: TypeSig,
 }
 
 impl Signature<RetType> for RetType {
-    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> RetType {
-        let mut byte = reader.le_u8();
-        let mut custom_mod = false;
-        if byte == (ElementType::CMOD_REQD as u8) {
-            custom_mod = true;
-            byte = reader.le_u8();
-        }
-
-        let mut by_ref = false;
-        let typed: ElementType;
-        if byte == (ElementType::Void as u8) {
-            typed = ElementType::Void;
-        } else if byte == (ElementType::ByRef as u8) {
-            byte = reader.le_u8();
-            typed = ElementType::from(byte);
-            by_ref = true;
-        } else if byte == (ElementType::TypedByRef as u8) {
-//            byte = reader.le_u8();
-            unimplemented!("parse typed by ref not implemented!");
-        } else {
-            typed = ElementType::from(byte);
-        }
-
-        RetType {
-            custom_mod,
+    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Result<RetType> {
+        let (custom_mods, by_ref, type_sig) = parse_param_type(reader, 0)?;
+        Ok(RetType {
+            custom_mods,
             by_ref,
-            typed,
-        }
+            type_sig,
+        })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Param {
-    pub custom_mod: bool,
+    pub custom_mods: Vec<CustomMod>,
     pub by_ref: bool,
-    pub typed: ElementType,
-    pub type_ind: usize,
+    pub type_sig: TypeSig,
 }
 
 impl Signature<Param> for Param {
-    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Param {
-        let mut byte = reader.le_u8();
+    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Result<Param> {
+        let (custom_mods, by_ref, type_sig) = parse_param_type(reader, 0)?;
+        Ok(Param {
+            custom_mods,
+            by_ref,
+            type_sig,
+        })
+    }
+}
+
+/// shared head of RetType and Param: modifiers, BYREF, then the type
+fn parse_param_type(reader: &mut BinaryReader, depth: u32) -> Result<(Vec<CustomMod>, bool, TypeSig)> {
+    let custom_mods = CustomMod::parse_list(reader)?;
+    let by_ref = reader.peek_u8()? == (ElementType::ByRef as u8);
+    if by_ref {
+        reader.le_u8()?;
+    }
+    let type_sig = TypeSig::parse_nested(reader, depth)?;
+    Ok((custom_mods, by_ref, type_sig))
+}
+
+/// one local of a LocalVarSig, modifiers and PINNED may come in any order before BYREF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalVar {
+    pub custom_mods: Vec<CustomMod>,
+    /// the GC must not move the object the local points into, e.g. C# `fixed`
+    pub pinned: bool,
+    pub by_ref: bool,
+    pub type_sig: TypeSig,
+}
 
-        let mut custom_mod = false;
-        if byte == (ElementType::CMOD_REQD as u8) { //TODO
-            custom_mod = true;
-            byte = reader.le_u8();
+impl Signature<LocalVar> for LocalVar {
+    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Result<LocalVar> {
+        let mut custom_mods = Vec::new();
+        let mut pinned = false;
+        loop {
+            custom_mods.extend(CustomMod::parse_list(reader)?);
+            if reader.peek_u8()? != (ElementType::Pinned as u8) {
+                break;
+            }
+            reader.le_u8()?;
+            pinned = true;
+        }
+        let by_ref = reader.peek_u8()? == (ElementType::ByRef as u8);
+        if by_ref {
+            reader.le_u8()?;
         }
+        let type_sig = TypeSig::parse_signature(reader, 0)?;
+        Ok(LocalVar {
+            custom_mods,
+            pinned,
+            by_ref,
+            type_sig,
+        })
+    }
+}
 
-        let mut by_ref = false;
+/// FieldSig of ECMA-335 II.23.2.4, the signature blob of a Field row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSig {
+    pub custom_mods: Vec<CustomMod>,
+    pub type_sig: TypeSig,
+}
 
-        let typed;
-        let type_ind = 0_usize;
-        if byte == (ElementType::ByRef as u8) {
-            by_ref = true;
-            byte = reader.le_u8();
-            typed = ElementType::from(byte);
-        } else if byte == (ElementType::TypedByRef as u8) {
-            unimplemented!("parse typed by ref not implemented!");
+impl Signature<FieldSig> for FieldSig {
+    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Result<FieldSig> {
+        let byte = reader.le_u8()?;
+        if byte != 0x06 {
+            return Err(Error::InvalidValue { what: "field signature kind", value: byte as u32 });
+        }
+        let custom_mods = CustomMod::parse_list(reader)?;
+        let type_sig = TypeSig::parse_signature(reader, 0)?;
+        Ok(FieldSig {
+            custom_mods,
+            type_sig,
+        })
+    }
+}
+
+/// PropertySig of ECMA-335 II.23.2.5, the getter's signature without a calling convention
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertySig {
+    pub has_this: bool,
+    pub param_count: u32,
+    pub custom_mods: Vec<CustomMod>,
+    pub type_sig: TypeSig,
+    /// index parameters of an indexer, empty for plain properties
+    pub params: Vec<Param>,
+}
+
+impl Signature<PropertySig> for PropertySig {
+    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Result<PropertySig> {
+        let byte = reader.le_u8()?;
+        if byte & !0x20 != 0x08 {
+            return Err(Error::InvalidValue { what: "property signature kind", value: byte as u32 });
+        }
+        let param_count = reader.compressed_u32()?;
+        let custom_mods = CustomMod::parse_list(reader)?;
+        let type_sig = TypeSig::parse_signature(reader, 0)?;
+        let mut params = Vec::new();
+        for _ in 0..param_count {
+            params.push(Param::parse_signature(reader, 0)?);
+        }
+        Ok(PropertySig {
+            has_this: byte & 0x20 != 0,
+            param_count,
+            custom_mods,
+            type_sig,
+            params,
+        })
+    }
+}
+
+/// LocalVarSig of ECMA-335 II.23.2.6, the StandAloneSig a fat method header points at
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LocalVarSig {
+    pub locals: Vec<LocalVar>,
+}
+
+impl Signature<LocalVarSig> for LocalVarSig {
+    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Result<LocalVarSig> {
+        let byte = reader.le_u8()?;
+        if byte != 0x07 {
+            return Err(Error::InvalidValue { what: "local signature kind", value: byte as u32 });
+        }
+        let count = reader.compressed_u32()?;
+        if count == 0 || count > 0xFFFE {
+            return Err(Error::InvalidValue { what: "local count", value: count });
+        }
+        let mut locals = Vec::new();
+        for _ in 0..count {
+            locals.push(LocalVar::parse_signature(reader, 0)?);
+        }
+        Ok(LocalVarSig { locals })
+    }
+}
+
+/// signature blob of a MemberRef row, a MethodRefSig or a FieldSig told apart by the FIELD byte
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemberRefSig {
+    /// MethodRefSig, equal to a MethodDefSig except for the params after SENTINEL at vararg call sites
+    Method(MethodDefSig),
+    Field(FieldSig),
+}
+
+impl Signature<MemberRefSig> for MemberRefSig {
+    fn parse_signature(reader: &mut BinaryReader, length: usize) -> Result<MemberRefSig> {
+        if reader.peek_u8()? == 0x06 {
+            Ok(MemberRefSig::Field(FieldSig::parse_signature(reader, length)?))
         } else {
-            typed = ElementType::from(byte);
+            Ok(MemberRefSig::Method(MethodDefSig::parse_signature(reader, length)?))
         }
-        Param {
-            custom_mod,
-            by_ref,
-            typed,
-            type_ind,
+    }
+}
+
+/// MethodSpec blob of ECMA-335 II.23.2.15, the type arguments of a generic method instantiation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSpecSig {
+    pub args: Vec<TypeSig>,
+}
+
+impl Signature<MethodSpecSig> for MethodSpecSig {
+    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Result<MethodSpecSig> {
+        let byte = reader.le_u8()?;
+        if byte != 0x0A {
+            return Err(Error::InvalidValue { what: "method spec signature kind", value: byte as u32 });
         }
+        let count = reader.compressed_u32()?;
+        let mut args = Vec::new();
+        for _ in 0..count {
+            args.push(TypeSig::parse_signature(reader, 0)?);
+        }
+        Ok(MethodSpecSig { args })
     }
 }
 
+/// generic method called through a MethodSpec, with the arguments for the
+/// Var and MVar placeholders of its signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodInstance {
+    /// MethodDef, or MemberRef for methods of other modules and of generic type instances
+    pub method: MethodDefOrRef,
+    /// arguments of the declaring type when the MemberRef parent is a generic TypeSpec
+    pub type_args: Vec<TypeSig>,
+    pub method_args: Vec<TypeSig>,
+}
+
+/// CMOD_REQD or CMOD_OPT and the type naming the modifier, e.g. IsVolatile,
+/// IsReadOnlyAttribute or CallConvCdecl
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomMod {
+    /// modreq, tools that do not understand the modifier must reject the member
+    pub required: bool,
+    pub modifier: TypeDefOrRef,
+}
+
+impl CustomMod {
+    /// every modifier at the reader's position, empty when the next byte starts something else
+    pub fn parse_list(reader: &mut BinaryReader) -> Result<Vec<CustomMod>> {
+        let mut mods = Vec::new();
+        loop {
+            let byte = reader.peek_u8()?;
+            let required = if byte == (ElementType::CMOD_REQD as u8) {
+                true
+            } else if byte == (ElementType::CMOD_OPT as u8) {
+                false
+            } else {
+                break;
+            };
+            reader.le_u8()?;
+            mods.push(CustomMod { required, modifier: read_type_def_or_ref(reader)? });
+        }
+        Ok(mods)
+    }
+}
+
+/// Type of ECMA-335 II.23.2.12. Void and TypedByRef only occur as return,
+/// parameter or pointer types but are kept as primitives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeSig {
+    /// Void, Boolean, Char, the sized integers and floats, IntPtr, UIntPtr, String, Object and TypedByRef
+    Primitive(ElementType),
+    Class(TypeDefOrRef),
+    ValueType(TypeDefOrRef),
+    Ptr(Box<TypeSig>),
+    /// single dimension, zero based
+    SZArray(Box<TypeSig>),
+    Array(Box<TypeSig>, ArrayShape),
+    GenericInst {
+        is_value_type: bool,
+        generic_type: TypeDefOrRef,
+        args: Vec<TypeSig>,
+    },
+    /// generic parameter of the enclosing type
+    Var(u32),
+    /// generic parameter of the method
+    MVar(u32),
+    FnPtr(Box<MethodDefSig>),
+    /// type preceded by custom modifiers, as inside PTR and SZARRAY
+    Modified {
+        custom_mods: Vec<CustomMod>,
+        inner: Box<TypeSig>,
+    },
+}
+
+impl Signature<TypeSig> for TypeSig {
+    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Result<TypeSig> {
+        TypeSig::parse_nested(reader, 0)
+    }
+}
+
+/// types nested deeper than this are rejected, a crafted blob of PTR bytes
+/// would otherwise recurse until the stack overflows
+pub const MAX_SIGNATURE_DEPTH: u32 = 64;
+
+impl TypeSig {
+    /// rest of the type after its leading element type byte
+    pub fn parse_element(reader: &mut BinaryReader, element: ElementType) -> Result<TypeSig> {
+        TypeSig::parse_element_nested(reader, element, 0)
+    }
+
+    /// Type inside `depth` enclosing types
+    fn parse_nested(reader: &mut BinaryReader, depth: u32) -> Result<TypeSig> {
+        if depth > MAX_SIGNATURE_DEPTH {
+            return Err(Error::InvalidValue { what: "signature nesting", value: depth });
+        }
+        let custom_mods = CustomMod::parse_list(reader)?;
+        let element = ElementType::try_from(reader.le_u8()?)?;
+        let sig = TypeSig::parse_element_nested(reader, element, depth)?;
+        if custom_mods.is_empty() {
+            Ok(sig)
+        } else {
+            Ok(TypeSig::Modified { custom_mods, inner: Box::new(sig) })
+        }
+    }
+
+    fn parse_element_nested(reader: &mut BinaryReader, element: ElementType, depth: u32) -> Result<TypeSig> {
+        use ElementType as E;
+        let depth = depth + 1;
+        let sig = match element {
+            E::Void | E::Boolean | E::Char | E::I1 | E::U1 | E::I2 | E::U2 | E::I4 | E::U4 | E::I8 | E::U8 |
+            E::F32 | E::F64 | E::String | E::Object | E::IntPtr | E::UIntPtr | E::TypedByRef => TypeSig::Primitive(element),
+            E::Class => TypeSig::Class(read_type_def_or_ref(reader)?),
+            E::ValueType => TypeSig::ValueType(read_type_def_or_ref(reader)?),
+            E::Ptr => TypeSig::Ptr(Box::new(TypeSig::parse_nested(reader, depth)?)),
+            E::SZAarray => TypeSig::SZArray(Box::new(TypeSig::parse_nested(reader, depth)?)),
+            E::Array => {
+                let element_type = TypeSig::parse_nested(reader, depth)?;
+                TypeSig::Array(Box::new(element_type), ArrayShape::parse(reader)?)
+            }
+            E::GenericInst => {
+                let is_value_type = match ElementType::try_from(reader.le_u8()?)? {
+                    E::Class => false,
+                    E::ValueType => true,
+                    kind => return Err(Error::InvalidValue { what: "generic instance kind", value: kind as u32 }),
+                };
+                let generic_type = read_type_def_or_ref(reader)?;
+                let count = reader.compressed_u32()?;
+                let mut args = Vec::new();
+                for _ in 0..count {
+                    args.push(TypeSig::parse_nested(reader, depth)?);
+                }
+                TypeSig::GenericInst { is_value_type, generic_type, args }
+            }
+            E::Var => TypeSig::Var(reader.compressed_u32()?),
+            E::Mvar => TypeSig::MVar(reader.compressed_u32()?),
+            E::FNPTR => TypeSig::FnPtr(Box::new(MethodDefSig::parse_nested(reader, depth)?)),
+            _ => return Err(Error::InvalidValue { what: "type signature element", value: element as u32 }),
+        };
+        Ok(sig)
+    }
+}
+
+impl TypeSig {
+    /// the type with Var(n) replaced by `type_args[n]` and MVar(n) by `method_args[n]`.
+    /// placeholders without an argument are kept, so class and method arguments may be
+    /// substituted separately
+    pub fn substitute(&self, type_args: &[TypeSig], method_args: &[TypeSig]) -> TypeSig {
+        let sub = |sig: &TypeSig| Box::new(sig.substitute(type_args, method_args));
+        match self {
+            TypeSig::Var(n) => type_args.get(*n as usize).cloned().unwrap_or(TypeSig::Var(*n)),
+            TypeSig::MVar(n) => method_args.get(*n as usize).cloned().unwrap_or(TypeSig::MVar(*n)),
+            TypeSig::Ptr(inner) => TypeSig::Ptr(sub(inner)),
+            TypeSig::SZArray(inner) => TypeSig::SZArray(sub(inner)),
+            TypeSig::Array(inner, shape) => TypeSig::Array(sub(inner), shape.clone()),
+            TypeSig::GenericInst { is_value_type, generic_type, args } => TypeSig::GenericInst {
+                is_value_type: *is_value_type,
+                generic_type: *generic_type,
+                args: args.iter().map(|a| a.substitute(type_args, method_args)).collect(),
+            },
+            TypeSig::FnPtr(sig) => TypeSig::FnPtr(Box::new(sig.substitute(type_args, method_args))),
+            TypeSig::Modified { custom_mods, inner } => TypeSig::Modified { custom_mods: custom_mods.clone(), inner: sub(inner) },
+            _ => self.clone(),
+        }
+    }
 
-#[derive(Copy, Clone, Debug)]
+    /// generic definition and type arguments of a GenericInst
+    pub fn generic_instance(&self) -> Option<(TypeDefOrRef, &[TypeSig])> {
+        match self {
+            TypeSig::GenericInst { generic_type, args, .. } => Some((*generic_type, args)),
+            TypeSig::Modified { inner, .. } => inner.generic_instance(),
+            _ => None,
+        }
+    }
+}
+
+/// TypeDefOrRefOrSpecEncoded, a TypeDefOrRef coded index as a compressed integer
+fn read_type_def_or_ref(reader: &mut BinaryReader) -> Result<TypeDefOrRef> {
+    let value = reader.compressed_u32()?;
+    TypeDefOrRef::decode(value).ok_or(Error::InvalidValue { what: "TypeDefOrRef in signature", value })
+}
+
+/// ArrayShape of II.23.2.13, dimensions past `sizes` and `lo_bounds` are unspecified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayShape {
+    pub rank: u32,
+    pub sizes: Vec<u32>,
+    pub lo_bounds: Vec<i32>,
+}
+
+impl ArrayShape {
+    pub fn parse(reader: &mut BinaryReader) -> Result<ArrayShape> {
+        let rank = reader.compressed_u32()?;
+        let num_sizes = reader.compressed_u32()?;
+        let mut sizes = Vec::new();
+        for _ in 0..num_sizes {
+            sizes.push(reader.compressed_u32()?);
+        }
+        let num_lo_bounds = reader.compressed_u32()?;
+        let mut lo_bounds = Vec::new();
+        for _ in 0..num_lo_bounds {
+            lo_bounds.push(reader.compressed_i32()?);
+        }
+        Ok(ArrayShape { rank, sizes, lo_bounds })
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[allow(non_camel_case_types)]
 pub enum ElementType {
     End = 0x00,
@@ -533,9 +1452,11 @@ impl From<&ElementType> for u8 {
 }
 
 
-impl From<u8> for ElementType {
-    fn from(v: u8) -> Self {
-        match v {
+impl TryFrom<u8> for ElementType {
+    type Error = Error;
+
+    fn try_from(v: u8) -> Result<Self> {
+        Ok(match v {
             0x00 => ElementType::End,
             0x01 => ElementType::Void,
             0x02 => ElementType::Boolean,
@@ -571,73 +1492,137 @@ impl From<u8> for ElementType {
             0x40 => ElementType::Modifier,
             0x41 => ElementType::Sentinel,
             0x45 => ElementType::Pinned,
-            _ => panic!("not implement convert"),
-        }
+            _ => return Err(Error::InvalidValue { what: "element type", value: v as u32 }),
+        })
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum MethodDefSigType {
     Default = 0x0,
+    //unmanaged conventions, only seen on function pointers
+    C = 0x1,
+    StdCall = 0x2,
+    ThisCall = 0x3,
+    FastCall = 0x4,
     VarArg = 0x5,
     Generic = 0x10,
 }
 
-impl From<u8> for MethodDefSigType {
-    fn from(v: u8) -> Self {
+impl TryFrom<u8> for MethodDefSigType {
+    type Error = Error;
+
+    fn try_from(v: u8) -> Result<Self> {
         match v {
-            0x0 => MethodDefSigType::Default,
-            0x5 => MethodDefSigType::VarArg,
-            0x10 => MethodDefSigType::Generic,
-            _ => panic!("invalid value for MethodDefSigType")
+            0x0 => Ok(MethodDefSigType::Default),
+            0x1 => Ok(MethodDefSigType::C),
+            0x2 => Ok(MethodDefSigType::StdCall),
+            0x3 => Ok(MethodDefSigType::ThisCall),
+            0x4 => Ok(MethodDefSigType::FastCall),
+            0x5 => Ok(MethodDefSigType::VarArg),
+            0x10 => Ok(MethodDefSigType::Generic),
+            _ => Err(Error::InvalidValue { what: "method signature kind", value: v as u32 })
         }
     }
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MethodDefSig {
     pub has_this: bool,
     pub explicit_this: bool,
     pub def_type: MethodDefSigType,
-    pub param_count: u8,
+    /// GenParamCount, 0 unless def_type is Generic
+    pub generic_param_count: u32,
+    pub param_count: u32,
     pub ret_type: RetType,
     pub params: Vec<Param>,
+    /// index of the first parameter after SENTINEL, the variable part of a vararg call site
+    pub sentinel: Option<u32>,
 
 }
 
 
 impl Signature<MethodDefSig> for MethodDefSig {
-    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> MethodDefSig {
-        let mut byte = reader.le_u8();
-        let mut has_this = false;
-        if byte == 0x20 {
-            has_this = true;
-            byte = reader.le_u8();
-        }
-        let mut explicit_this = false;
-        if byte == 0x40 {
-            explicit_this = true;
-            byte = reader.le_u8();
-        }
+    fn parse_signature(reader: &mut BinaryReader, _length: usize) -> Result<MethodDefSig> {
+        MethodDefSig::parse_nested(reader, 0)
+    }
+}
 
-        let def_type = MethodDefSigType::from(byte);
-        let param_count = reader.le_u8();
+impl MethodDefSig {
+    /// signature of a FNPTR inside `depth` enclosing types, 0 for a method's own blob
+    fn parse_nested(reader: &mut BinaryReader, depth: u32) -> Result<MethodDefSig> {
+        //HASTHIS and EXPLICITTHIS are flags on the same byte as the calling convention
+        let byte = reader.le_u8()?;
+        let has_this = byte & 0x20 != 0;
+        let explicit_this = byte & 0x40 != 0;
 
-        let ret_type = RetType::parse_signature(reader, 0);
+        let def_type = MethodDefSigType::try_from(byte & 0x1f)?;
+        let generic_param_count = if def_type == MethodDefSigType::Generic { reader.compressed_u32()? } else { 0 };
+        let param_count = reader.compressed_u32()?;
+
+        let (custom_mods, by_ref, type_sig) = parse_param_type(reader, depth)?;
+        let ret_type = RetType { custom_mods, by_ref, type_sig };
 
         let mut params = Vec::new();
-        for _ in 0..param_count {
-            params.push(Param::parse_signature(reader, 0));
+        let mut sentinel = None;
+        for index in 0..param_count {
+            if reader.peek_u8()? == (ElementType::Sentinel as u8) {
+                reader.le_u8()?;
+                sentinel = Some(index);
+            }
+            let (custom_mods, by_ref, type_sig) = parse_param_type(reader, depth)?;
+            params.push(Param { custom_mods, by_ref, type_sig });
         }
 
-        MethodDefSig {
+        Ok(MethodDefSig {
             has_this,
             explicit_this,
             def_type,
+            generic_param_count,
             param_count,
             ret_type,
             params,
+            sentinel,
+        })
+    }
+
+    /// params declared by the method, all of them unless this is a vararg call site
+    pub fn fixed_params(&self) -> &[Param] {
+        match self.sentinel {
+            Some(index) => &self.params[..index as usize],
+            None => &self.params,
+        }
+    }
+
+    /// extra arguments a vararg call site passes after SENTINEL
+    pub fn vararg_params(&self) -> &[Param] {
+        match self.sentinel {
+            Some(index) => &self.params[index as usize..],
+            None => &[],
+        }
+    }
+
+    /// a call through the MethodRefSig `call_site` binds to a method with this signature.
+    /// types are compared structurally, so TypeDefOrRef operands only match within one module
+    pub fn matches_call_site(&self, call_site: &MethodDefSig) -> bool {
+        if call_site.sentinel.is_some() && self.def_type != MethodDefSigType::VarArg {
+            return false;
+        }
+        self.has_this == call_site.has_this
+            && self.explicit_this == call_site.explicit_this
+            && self.def_type == call_site.def_type
+            && self.generic_param_count == call_site.generic_param_count
+            && self.ret_type == call_site.ret_type
+            && self.fixed_params() == call_site.fixed_params()
+    }
+    /// signature of the instantiated method, see TypeSig::substitute
+    pub fn substitute(&self, type_args: &[TypeSig], method_args: &[TypeSig]) -> MethodDefSig {
+        let mut sig = self.clone();
+        sig.ret_type.type_sig = self.ret_type.type_sig.substitute(type_args, method_args);
+        for param in sig.params.iter_mut() {
+            param.type_sig = param.type_sig.substitute(type_args, method_args);
         }
+        sig
     }
 }
\ No newline at end of file
@@ -0,0 +1,329 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::str;
+
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+
+use crate::data::Guid;
+use crate::debugdir::*;
+use crate::error::*;
+use crate::loader::DllData;
+use crate::meta::*;
+use crate::reader::*;
+use crate::tbl::*;
+
+/// start and end line of a hidden sequence point
+pub const HIDDEN_LINE: u32 = 0x00FE_EFEE;
+
+pub fn load_pdb(file_path: &str) -> Result<PdbFile<'static>> {
+    let mut data = Vec::new();
+    File::open(Path::new(file_path))?.read_to_end(&mut data)?;
+    PdbFile::new(data)
+}
+
+/// #Pdb stream of a portable pdb
+#[derive(Debug, Default)]
+pub struct PdbStream {
+    /// guid followed by the stamp, matches the CodeView entry of the image
+    pub id: [u8; 20],
+    pub entry_point: MetadataToken,
+    pub referenced_type_system_tables: u64,
+    /// row counts of the referenced tables, indexed by table id
+    pub type_system_table_rows: Vec<u32>,
+}
+
+impl PdbStream {
+    pub fn parse(reader: &mut BinaryReader) -> Result<PdbStream> {
+        let mut id = [0u8; 20];
+        id.copy_from_slice(reader.bytes(20)?);
+        let entry_point = MetadataToken(reader.le_u32()?);
+        let referenced_type_system_tables = reader.le_u64()?;
+        let mut type_system_table_rows = vec![0; 64];
+        for (id, rows) in type_system_table_rows.iter_mut().enumerate() {
+            if referenced_type_system_tables & (1 << id) != 0 {
+                *rows = reader.le_u32()?;
+            }
+        }
+        Ok(PdbStream {
+            id,
+            entry_point,
+            referenced_type_system_tables,
+            type_system_table_rows,
+        })
+    }
+
+    pub fn guid(&self) -> Guid {
+        let mut guid = [0u8; 16];
+        guid.copy_from_slice(&self.id[..16]);
+        Guid(guid)
+    }
+
+    pub fn stamp(&self) -> u32 {
+        u32::from_le_bytes([self.id[16], self.id[17], self.id[18], self.id[19]])
+    }
+
+    /// the pdb was written for the image the debug directory entry belongs to
+    pub fn matches(&self, entry: &DebugDirectoryEntry) -> bool {
+        match &entry.info {
+            DebugInfo::CodeView(cv) => cv.guid == self.guid() && entry.time_date_stamp == self.stamp(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SequencePoint {
+    /// row id in the Document table
+    pub document: u32,
+    pub il_offset: u32,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+impl SequencePoint {
+    /// compiler generated code without a source location
+    pub fn is_hidden(&self) -> bool {
+        self.start_line == HIDDEN_LINE
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// standalone or embedded portable pdb, an ECMA-335 metadata root with a #Pdb stream
+#[derive(Debug, Default)]
+pub struct PdbFile<'a> {
+    pub data: DllData<'a>,
+    pub meta: CLIMetaData,
+    pub pdb_stream: PdbStream,
+    pub tilde_stream: CLITildeStream,
+    pub string_stream: CLIStringStream,
+    pub guid_heap: GuidHeap,
+    pub blob_heap: BlobHeap,
+
+    pub tbl_document: CLITable<MetaDocument>,
+    pub tbl_method_debug_information: CLITable<MetaMethodDebugInformation>,
+    pub tbl_local_scope: CLITable<MetaLocalScope>,
+    pub tbl_local_variable: CLITable<MetaLocalVariable>,
+    pub tbl_local_constant: CLITable<MetaLocalConstant>,
+    pub tbl_import_scope: CLITable<MetaImportScope>,
+    pub tbl_state_machine_method: CLITable<MetaStateMachineMethod>,
+    pub tbl_custom_debug_information: CLITable<MetaCustomDebugInformation>,
+}
+
+impl PdbFile<'static> {
+    pub fn new(dat: Vec<u8>) -> Result<PdbFile<'static>> {
+        PdbFile::parse(DllData::Owned(dat))
+    }
+
+    /// pdb of an EmbeddedPortablePdb debug directory entry, raw deflate after the MPDB header
+    pub fn from_embedded(uncompressed_size: u32, compressed: &[u8]) -> Result<PdbFile<'static>> {
+        let data = decompress_to_vec_with_limit(compressed, uncompressed_size as usize)
+            .map_err(|_| Error::InvalidValue { what: "embedded pdb deflate stream", value: uncompressed_size })?;
+        if data.len() != uncompressed_size as usize {
+            return Err(Error::InvalidValue { what: "embedded pdb size", value: data.len() as u32 });
+        }
+        PdbFile::new(data)
+    }
+}
+
+impl<'a> PdbFile<'a> {
+    pub fn from_bytes(dat: &'a [u8]) -> Result<PdbFile<'a>> {
+        PdbFile::parse(DllData::Borrowed(dat))
+    }
+
+    fn parse(dat: DllData<'a>) -> Result<PdbFile<'a>> {
+        let reader = &mut BinaryReader::new(&dat);
+        let meta = CLIMetaData::parse(reader)?;
+        let stream = |name: &str| -> Option<(usize, usize)> {
+            meta.stream_header.iter().find(|s| s.name == name)
+                .map(|s| (meta.meta_pos + s.offset as usize, s.size as usize))
+        };
+
+        let (pdb_start, pdb_size) = stream("#Pdb").ok_or(Error::Unsupported("metadata without a #Pdb stream"))?;
+        let pdb_stream = PdbStream::parse(&mut reader.sub_reader(pdb_start, pdb_size)?)?;
+
+        let tables = meta.tables_stream().ok_or(Error::Unsupported("portable pdb without a #~ stream"))?;
+        let tables_reader = &mut reader.sub_reader(meta.meta_pos + tables.offset as usize, tables.size as usize)?;
+        let tilde_stream = CLITildeStream::parse_with_type_system(tables_reader, pdb_stream.type_system_table_rows.clone())?;
+
+        let (str_start, str_size) = stream("#Strings").unwrap_or((0, 0));
+        let string_stream = CLIStringStream { base: str_start, size: str_size };
+        let (blob_start, blob_size) = stream("#Blob").unwrap_or((0, 0));
+        let blob_heap = BlobHeap { base: blob_start, size: blob_size };
+        let guid_heap = match stream("#GUID") {
+            Some(info) => GuidHeap::parse(reader, info)?,
+            None => GuidHeap::default(),
+        };
+
+        let tilde = &tilde_stream;
+        string_stream.view(reader.raw_data)?;
+        let tbl_document = MetaDocument::parse_table(reader, tilde)?;
+        let tbl_method_debug_information = MetaMethodDebugInformation::parse_table(reader, tilde)?;
+        let tbl_local_scope = MetaLocalScope::parse_table(reader, tilde)?;
+        let tbl_local_variable = MetaLocalVariable::parse_table(reader, tilde)?;
+        let tbl_local_constant = MetaLocalConstant::parse_table(reader, tilde)?;
+        let tbl_import_scope = MetaImportScope::parse_table(reader, tilde)?;
+        let tbl_state_machine_method = MetaStateMachineMethod::parse_table(reader, tilde)?;
+        let tbl_custom_debug_information = MetaCustomDebugInformation::parse_table(reader, tilde)?;
+
+        Ok(PdbFile {
+            data: dat,
+            meta,
+            pdb_stream,
+            tilde_stream,
+            string_stream,
+            guid_heap,
+            blob_heap,
+            tbl_document,
+            tbl_method_debug_information,
+            tbl_local_scope,
+            tbl_local_variable,
+            tbl_local_constant,
+            tbl_import_scope,
+            tbl_state_machine_method,
+            tbl_custom_debug_information,
+        })
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn string(&self, offset: u32) -> Result<&str> {
+        self.string_stream.view(self.bytes())?.get(offset)
+    }
+
+    /// full path of a document, stored as a separator and the blob ids of its parts
+    pub fn document_name(&self, rid: u32) -> Result<String> {
+        let document = self.tbl_document.get_row(rid).ok_or(Error::InvalidValue { what: "document row", value: rid })?;
+        let blob = &mut BinaryReader::new(self.blob_heap.get(self.bytes(), document.name)?);
+        let separator = blob.le_u8()?;
+        if separator >= 0x80 {
+            return Err(Error::InvalidValue { what: "document name separator", value: separator as u32 });
+        }
+
+        let mut name = String::new();
+        let mut first = true;
+        while blob.remaining() > 0 {
+            let part = blob.compressed_u32()?;
+            if !first && separator != 0 {
+                name.push(separator as char);
+            }
+            first = false;
+            let bytes = self.blob_heap.get(self.bytes(), part)?;
+            let text = str::from_utf8(bytes).map_err(|_| Error::InvalidUtf8 { offset: self.blob_heap.base + part as usize })?;
+            name.push_str(text);
+        }
+        Ok(name)
+    }
+
+    /// sequence points of a MethodDef in IL offset order, empty for methods without debug info
+    pub fn sequence_points(&self, method: MetadataToken) -> Result<Vec<SequencePoint>> {
+        if method.table() != Some(CLITableId::MethodDef) {
+            return Err(Error::InvalidValue { what: "method token", value: method.0 });
+        }
+        let info = match self.tbl_method_debug_information.get_row(method.rid()) {
+            Some(info) if info.sequence_points != 0 => info,
+            _ => return Ok(Vec::new()),
+        };
+        let blob = &mut BinaryReader::new(self.blob_heap.get(self.bytes(), info.sequence_points)?);
+        decode_sequence_points(blob, info.document)
+    }
+
+    /// source line of an IL offset, e.g. of a stack frame. hidden points are skipped
+    /// so the offset maps to the closest visible statement before it
+    pub fn source_location(&self, method: MetadataToken, il_offset: u32) -> Result<Option<SourceLocation>> {
+        let mut best = None;
+        for point in self.sequence_points(method)? {
+            if point.il_offset > il_offset {
+                break;
+            }
+            if !point.is_hidden() {
+                best = Some(point);
+            }
+        }
+        match best {
+            Some(point) => Ok(Some(SourceLocation {
+                file: self.document_name(point.document)?,
+                line: point.start_line,
+                column: point.start_column,
+            })),
+            None => Ok(None),
+        }
+    }
+}
+
+/// SequencePoints blob, see the portable pdb spec. lines and columns of visible
+/// points are deltas against the previous visible point
+pub(crate) fn decode_sequence_points(blob: &mut BinaryReader, document: u32) -> Result<Vec<SequencePoint>> {
+    //StandAloneSig of the locals, LocalVariable rows carry the names
+    blob.compressed_u32()?;
+    let mut document = if document == 0 { blob.compressed_u32()? } else { document };
+
+    let mut points = Vec::new();
+    let mut il_offset = 0u32;
+    let mut previous: Option<(u32, u32)> = None;
+    while blob.remaining() > 0 {
+        let delta_il = blob.compressed_u32()?;
+        //document record, switches the file of the following points
+        if delta_il == 0 && !points.is_empty() {
+            document = blob.compressed_u32()?;
+            continue;
+        }
+        il_offset = il_offset.checked_add(delta_il).ok_or(Error::InvalidValue { what: "sequence point IL offset", value: delta_il })?;
+
+        let delta_lines = blob.compressed_u32()?;
+        let delta_columns = if delta_lines == 0 {
+            blob.compressed_u32()? as i64
+        } else {
+            blob.compressed_i32()? as i64
+        };
+        if delta_lines == 0 && delta_columns == 0 {
+            points.push(SequencePoint {
+                document,
+                il_offset,
+                start_line: HIDDEN_LINE,
+                start_column: 0,
+                end_line: HIDDEN_LINE,
+                end_column: 0,
+            });
+            continue;
+        }
+
+        let (start_line, start_column) = match previous {
+            Some((line, column)) => (
+                add_delta(line, blob.compressed_i32()? as i64, "sequence point line")?,
+                add_delta(column, blob.compressed_i32()? as i64, "sequence point column")?,
+            ),
+            None => (blob.compressed_u32()?, blob.compressed_u32()?),
+        };
+        previous = Some((start_line, start_column));
+        points.push(SequencePoint {
+            document,
+            il_offset,
+            start_line,
+            start_column,
+            end_line: add_delta(start_line, delta_lines as i64, "sequence point end line")?,
+            end_column: add_delta(start_column, delta_columns, "sequence point end column")?,
+        });
+    }
+    Ok(points)
+}
+
+/// `value + delta` of a line or column, negative and overflowing results are corrupt
+fn add_delta(value: u32, delta: i64, what: &'static str) -> Result<u32> {
+    let sum = value as i64 + delta;
+    if sum < 0 || sum > u32::MAX as i64 {
+        return Err(Error::InvalidValue { what, value: sum as u32 });
+    }
+    Ok(sum as u32)
+}
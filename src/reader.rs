@@ -1,8 +1,13 @@
 #![allow(dead_code)]
 
+use std::fmt;
+use std::result;
 use std::str;
 use std::intrinsics::transmute;
 
+use crate::data::Guid;
+use crate::error::*;
+
 #[derive(Debug,Default)]
 pub struct DataPointer{
     pub rva: u32,
@@ -18,9 +23,36 @@ impl DataPointer{
     }
 }
 
+/// a read went past the end of the reader's slice
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReadError {
+    /// offset in the whole image where the read started
+    pub offset: usize,
+    /// bytes the read needed
+    pub len: usize,
+}
+
+pub type ReadResult<T> = result::Result<T, ReadError>;
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "can not read {} bytes at {:#x}", self.len, self.offset)
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+impl From<ReadError> for Error {
+    fn from(e: ReadError) -> Self {
+        Error::Truncated { offset: e.offset }
+    }
+}
+
 pub struct BinaryReader<'a> {
     pub raw_data: &'a [u8],
     pub pos: usize,
+    /// offset of raw_data[0] in the whole image
+    pub base: usize,
 }
 
 impl<'a> BinaryReader<'a> {
@@ -28,72 +60,110 @@ impl<'a> BinaryReader<'a> {
         BinaryReader {
             raw_data: data,
             pos: 0,
+            base: 0,
+        }
+    }
+
+    /// reader limited to `len` bytes at `start` of this reader, reads never go past that range
+    pub fn sub_reader(&self, start: usize, len: usize) -> ReadResult<BinaryReader<'a>> {
+        let end = start.checked_add(len).filter(|&end| end <= self.raw_data.len());
+        match end {
+            Some(end) => Ok(BinaryReader {
+                raw_data: &self.raw_data[start..end],
+                pos: 0,
+                base: self.base + start,
+            }),
+            None => Err(ReadError { offset: self.base + start, len }),
+        }
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.raw_data.len().saturating_sub(self.pos)
+    }
+
+    #[inline]
+    pub fn abs_pos(&self) -> usize {
+        self.base + self.pos
+    }
+
+    #[inline]
+    fn check(&self, len: usize) -> ReadResult<usize> {
+        if self.remaining() < len {
+            Err(ReadError { offset: self.abs_pos(), len })
+        } else {
+            Ok(self.pos)
         }
     }
 
-    pub fn le_u8(self: &mut Self) -> u8 {
-        let ret = self.raw_data[self.pos];
+    pub fn bytes(&mut self, len: usize) -> ReadResult<&'a [u8]> {
+        let pos = self.check(len)?;
+        self.pos += len;
+        Ok(&self.raw_data[pos..pos + len])
+    }
+
+    pub fn le_u8(self: &mut Self) -> ReadResult<u8> {
+        let pos = self.check(1)?;
         self.pos += 1;
-        ret
+        Ok(self.raw_data[pos])
+    }
+
+    /// next byte without consuming it
+    pub fn peek_u8(&self) -> ReadResult<u8> {
+        let pos = self.check(1)?;
+        Ok(self.raw_data[pos])
     }
 
 
-    pub fn le_u16(self: &mut Self) -> u16 {
+    pub fn le_u16(self: &mut Self) -> ReadResult<u16> {
         let dat = self.raw_data;
-        let pos = self.pos;
+        let pos = self.check(2)?;
         let ret = ((dat[pos + 1] as u16) << 8) + dat[pos] as u16;
         self.pos += 2;
-        ret
+        Ok(ret)
     }
 
-    pub fn le_u32(self: &mut Self) -> u32 {
+    pub fn le_u32(self: &mut Self) -> ReadResult<u32> {
         let dat = self.raw_data;
-        let pos = self.pos;
+        let pos = self.check(4)?;
         let ret = ((dat[pos + 3] as u32) << 24) + ((dat[pos + 2] as u32) << 16) + ((dat[pos + 1] as u32) << 8) + dat[pos] as u32;
         self.pos += 4;
-        ret
+        Ok(ret)
     }
 
-    pub fn le_u64(&mut self)-> u64{
+    pub fn le_u64(&mut self)-> ReadResult<u64>{
         let i = self.raw_data;
-        let pos = self.pos;
+        let pos = self.check(8)?;
         let ret = ((i[pos+7] as u64) << 56) + ((i[pos+6] as u64) << 48) + ((i[pos+5] as u64) << 40) + ((i[pos+4] as u64) << 32) + ((i[pos+3] as u64) << 24)
             + ((i[pos+2] as u64) << 16) + ((i[pos+1] as u64) << 8) + i[pos+0] as u64;
         self.pos +=8;
-        ret
+        Ok(ret)
     }
 
-    pub fn le_i8(&mut self)->i8{
-        let ret = self.raw_data[self.pos];
+    pub fn le_i8(&mut self)->ReadResult<i8>{
+        let pos = self.check(1)?;
+        let ret = self.raw_data[pos];
         self.pos +=1;
-        unsafe {transmute(ret)}
+        Ok(unsafe {transmute(ret)})
     }
 
-    pub fn le_i32(&mut self)->i32{
-        let pos = self.pos;
+    pub fn le_i32(&mut self)->ReadResult<i32>{
         let dat = self.raw_data;
+        let pos = self.check(4)?;
         let ret = unsafe{ transmute::<[u8;4],i32>([dat[pos],dat[pos+1],dat[pos+2],dat[pos+3]])};
         self.pos+=4;
-        ret
-    }
-
-    pub fn tag_index(&mut self)->(u8,usize){
-        let tag = self.le_u8();
-
-        let dat = self.raw_data;
-        let pos = self.pos;
-        let ind = ((dat[pos] as u32) << 16) + ((dat[pos+1] as u32) << 8) + (dat[pos+2] as u32);
-        self.pos +=3;
-        (tag,ind as usize)
+        Ok(ret)
     }
 
-    pub fn ate(self: &mut Self, bytes: usize) {
+    pub fn ate(self: &mut Self, bytes: usize) -> ReadResult<()> {
+        self.check(bytes)?;
         self.pos += bytes;
+        Ok(())
     }
 
-    pub fn le_uint(self:&mut Self,byte:u8)->u32{
+    pub fn le_uint(self:&mut Self,byte:u8)->ReadResult<u32>{
         if byte == 2 {
-            self.le_u16() as u32
+            Ok(self.le_u16()? as u32)
         }else{
             self.le_u32()
         }
@@ -103,7 +173,6 @@ impl<'a> BinaryReader<'a> {
         let mut match_count = 0;
         let tag_count = tags.len();
         let mut pos = self.pos;
-        ;
         let dat = self.raw_data;
         let dat_count = dat.len();
         let mut suc = false;
@@ -153,23 +222,20 @@ impl<'a> BinaryReader<'a> {
         }
     }
 
-    pub fn str(self: &mut Self, bytes: usize) -> String {
-        let dat:&[u8] = self.raw_data;
-        let pos = self.pos;
-        let npos = pos + bytes;
-        let ret:& [u8] = &dat[pos..npos];
-        self.pos = npos;
+    pub fn str(self: &mut Self, bytes: usize) -> Result<String> {
+        let pos = self.abs_pos();
+        let ret:&[u8] = self.bytes(bytes)?;
 
         let ret:&str = match str::from_utf8(ret) {
             Ok(v)=>{v},
-            Err(_)=>panic!("parse string failed"),
+            Err(_)=>return Err(Error::InvalidUtf8 { offset: pos }),
         };
-        String::from(ret)
+        Ok(String::from(ret))
     }
 
-    pub fn str_read(self:&mut Self) ->Option<String>{
+    pub fn str_read(self:&mut Self) ->Result<Option<String>>{
         let data = self.raw_data;
-        let mut pos = self.pos;
+        let mut pos = self.check(1)?;
         let ret= if data[pos] == 0 {
             Option::None
         }else{
@@ -181,16 +247,20 @@ impl<'a> BinaryReader<'a> {
                 }
                 pos +=1;
             };
-            let str = unsafe{str::from_utf8_unchecked(&data[self.pos..pos])};
+            let str = match str::from_utf8(&data[self.pos..pos]) {
+                Ok(v)=>{v},
+                Err(_)=>return Err(Error::InvalidUtf8 { offset: self.abs_pos() }),
+            };
             self.pos = pos+1;
             Some(String::from(str))
         };
-        ret
+        Ok(ret)
     }
 
-    pub fn str_pad(self: & mut Self) -> String {
+    pub fn str_pad(self: & mut Self) -> ReadResult<String> {
         let dat = self.raw_data;
-        let mut pos = self.pos;
+        let start = self.check(1)?;
+        let mut pos = start;
 
         let dat_count = dat.len();
 
@@ -200,27 +270,86 @@ impl<'a> BinaryReader<'a> {
             }
             pos += 1;
         }
-        let byte_len = pos - self.pos;
-        let str = unsafe{str::from_utf8_unchecked(&dat[self.pos..pos])};
+        if pos == dat_count {
+            return Err(ReadError { offset: self.abs_pos(), len: pos - start + 1 });
+        }
+        let byte_len = pos - start;
+        let str = String::from_utf8_lossy(&dat[start..pos]).into_owned();
         let pos_offset = (3- byte_len %4) %4;
-        self.pos = pos + pos_offset + 1;
+        self.pos = pos + 1;
+        self.ate(pos_offset)?;
 
-        String::from(str)
+        Ok(str)
     }
 
-    pub fn data_pointer(&mut self)->DataPointer{
-        let rva = self.le_u32();
-        let size = self.le_u32();
-        DataPointer{
+    /// ECMA-335 II.23.2 compressed unsigned integer, 1, 2 or 4 bytes big endian
+    pub fn compressed_u32(&mut self) -> Result<u32> {
+        let b0 = self.le_u8()? as u32;
+        let value = if b0 & 0x80 == 0 {
+            b0
+        } else if b0 & 0xC0 == 0x80 {
+            (b0 & 0x3F) << 8 | self.le_u8()? as u32
+        } else if b0 & 0xE0 == 0xC0 {
+            let b = self.bytes(3)?;
+            (b0 & 0x1F) << 24 | (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32
+        } else {
+            return Err(Error::InvalidValue { what: "compressed integer", value: b0 });
+        };
+        Ok(value)
+    }
+
+    /// ECMA-335 II.23.2 compressed signed integer, the sign bit is rotated into bit 0
+    pub fn compressed_i32(&mut self) -> Result<i32> {
+        let b0 = self.raw_data.get(self.pos).copied().unwrap_or(0);
+        let bits = if b0 & 0x80 == 0 {
+            7
+        } else if b0 & 0xC0 == 0x80 {
+            14
+        } else {
+            29
+        };
+        let value = self.compressed_u32()?;
+        let mut signed = (value >> 1) as i32;
+        if value & 1 != 0 {
+            signed -= 1 << (bits - 1);
+        }
+        Ok(signed)
+    }
+
+    pub fn data_pointer(&mut self)->ReadResult<DataPointer>{
+        let rva = self.le_u32()?;
+        let size = self.le_u32()?;
+        Ok(DataPointer{
             rva:rva,
             size:size
+        })
+    }
+
+    /// bytes up to the next NUL, the NUL is consumed but not returned
+    pub fn c_str(&mut self) -> ReadResult<&'a [u8]> {
+        let start = self.check(1)?;
+        match self.raw_data[start..].iter().position(|&b| b == 0) {
+            Some(len) => {
+                self.pos = start + len + 1;
+                Ok(&self.raw_data[start..start + len])
+            }
+            None => Err(ReadError { offset: self.abs_pos(), len: self.remaining() + 1 }),
         }
     }
 
+    pub fn guid(&mut self) -> ReadResult<Guid> {
+        let mut guid = [0u8; 16];
+        guid.copy_from_slice(self.bytes(16)?);
+        Ok(Guid(guid))
+    }
+
     pub fn tag(self: &mut Self, tags: &[u8]) -> bool {
         let dat = self.raw_data;
         let pos = self.pos;
         let tag_len = tags.len();
+        if self.remaining() < tag_len {
+            return false;
+        }
 
         let mut suc = true;
         for t in 0..tag_len {
@@ -235,9 +364,11 @@ impl<'a> BinaryReader<'a> {
         suc
     }
 
-    pub fn tag_panic(self: &mut Self, tags: &[u8]) {
-        if !self.tag(tags) {
-            panic!("can not read tags: {:?}", tags);
+    pub fn expect_tag(self: &mut Self, tags: &'static [u8]) -> Result<()> {
+        if self.tag(tags) {
+            Ok(())
+        } else {
+            Err(Error::BadSignature { offset: self.abs_pos(), expected: tags })
         }
     }
     #[inline]
@@ -249,12 +380,12 @@ impl<'a> BinaryReader<'a> {
         (*self).pos += off;
     }
 
-    pub fn repeat<T>(&mut self, f: fn(&mut Self) -> T, count: u32) -> Vec<T> {
+    pub fn repeat<T, E>(&mut self, f: fn(&mut Self) -> result::Result<T, E>, count: u32) -> result::Result<Vec<T>, E> {
         let mut ret: Vec<T> = Vec::new();
         for _ in 0..count {
-            let v = f(self);
+            let v = f(self)?;
             ret.push(v);
         }
-        ret
+        Ok(ret)
     }
-}
\ No newline at end of file
+}
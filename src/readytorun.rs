@@ -0,0 +1,280 @@
+use crate::error::*;
+use crate::meta::CLIData;
+use crate::reader::*;
+use crate::winpe::{Machine, WinPe};
+
+pub const READYTORUN_FLAG_PLATFORM_NEUTRAL_SOURCE: u32 = 0x0000_0001;
+pub const READYTORUN_FLAG_SKIP_TYPE_VALIDATION: u32 = 0x0000_0002;
+pub const READYTORUN_FLAG_PARTIAL: u32 = 0x0000_0004;
+pub const READYTORUN_FLAG_NONSHARED_PINVOKE_STUBS: u32 = 0x0000_0008;
+pub const READYTORUN_FLAG_EMBEDDED_MSIL: u32 = 0x0000_0010;
+pub const READYTORUN_FLAG_COMPONENT: u32 = 0x0000_0020;
+pub const READYTORUN_FLAG_MULTIMODULE_VERSION_BUBBLE: u32 = 0x0000_0040;
+pub const READYTORUN_FLAG_UNRELATED_R2R_CODE: u32 = 0x0000_0080;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReadyToRunSectionType {
+    CompilerIdentifier = 100,
+    ImportSections = 101,
+    RuntimeFunctions = 102,
+    MethodDefEntryPoints = 103,
+    ExceptionInfo = 104,
+    DebugInfo = 105,
+    DelayLoadMethodCallThunks = 106,
+    AvailableTypes = 108,
+    InstanceMethodEntryPoints = 109,
+    InliningInfo = 110,
+    ProfileDataInfo = 111,
+    ManifestMetadata = 112,
+    AttributePresence = 113,
+    InliningInfo2 = 114,
+    ComponentAssemblies = 115,
+    OwnerCompositeExecutable = 116,
+    PgoInstrumentationData = 117,
+    ManifestAssemblyMvids = 118,
+    CrossModuleInlineInfo = 119,
+    HotColdMap = 120,
+    MethodIsGenericMap = 121,
+    EnclosingTypeMap = 122,
+    TypeGenericInfoMap = 123,
+    Unknown = 0,
+}
+
+impl From<u32> for ReadyToRunSectionType {
+    fn from(v: u32) -> Self {
+        use ReadyToRunSectionType::*;
+        match v {
+            100 => CompilerIdentifier,
+            101 => ImportSections,
+            102 => RuntimeFunctions,
+            103 => MethodDefEntryPoints,
+            104 => ExceptionInfo,
+            105 => DebugInfo,
+            106 => DelayLoadMethodCallThunks,
+            108 => AvailableTypes,
+            109 => InstanceMethodEntryPoints,
+            110 => InliningInfo,
+            111 => ProfileDataInfo,
+            112 => ManifestMetadata,
+            113 => AttributePresence,
+            114 => InliningInfo2,
+            115 => ComponentAssemblies,
+            116 => OwnerCompositeExecutable,
+            117 => PgoInstrumentationData,
+            118 => ManifestAssemblyMvids,
+            119 => CrossModuleInlineInfo,
+            120 => HotColdMap,
+            121 => MethodIsGenericMap,
+            122 => EnclosingTypeMap,
+            123 => TypeGenericInfoMap,
+            _ => Unknown,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ReadyToRunSection {
+    pub section_type: u32,
+    pub section: DataPointer,
+}
+
+impl ReadyToRunSection {
+    pub fn get_type(&self) -> ReadyToRunSectionType {
+        ReadyToRunSectionType::from(self.section_type)
+    }
+}
+
+/// entry of the ComponentAssemblies section of a composite image
+#[derive(Debug)]
+pub struct ComponentAssembly {
+    pub cor_header: DataPointer,
+    pub ready_to_run_core_header: DataPointer,
+}
+
+/// precompiled code of a MethodDef
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MethodEntryPoint {
+    pub runtime_function_index: u32,
+    /// rva of the native code
+    pub begin_rva: u32,
+    /// fixups the runtime resolves before the code may run
+    pub has_fixups: bool,
+}
+
+/// READYTORUN_HEADER the CLI header's ManagedNativeHeader points at
+#[derive(Debug)]
+pub struct ReadyToRunHeader {
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub flags: u32,
+    pub sections: Vec<ReadyToRunSection>,
+}
+
+impl ReadyToRunHeader {
+    pub fn parse(reader: &mut BinaryReader) -> Result<ReadyToRunHeader> {
+        reader.expect_tag(b"RTR\0")?;
+        let major_version = reader.le_u16()?;
+        let minor_version = reader.le_u16()?;
+        let flags = reader.le_u32()?;
+        let num_sections = reader.le_u32()?;
+        let sections = reader.repeat(|reader| -> ReadResult<ReadyToRunSection> {
+            Ok(ReadyToRunSection {
+                section_type: reader.le_u32()?,
+                section: reader.data_pointer()?,
+            })
+        }, num_sections)?;
+        Ok(ReadyToRunHeader {
+            major_version,
+            minor_version,
+            flags,
+            sections,
+        })
+    }
+
+    pub fn is_component(&self) -> bool {
+        self.flags & READYTORUN_FLAG_COMPONENT != 0
+    }
+
+    pub fn get_section(&self, section_type: ReadyToRunSectionType) -> Option<&DataPointer> {
+        self.sections.iter().find(|s| s.get_type() == section_type).map(|s| &s.section)
+    }
+
+    fn section_reader<'a>(&self, pe: &WinPe, data: &'a [u8], section_type: ReadyToRunSectionType) -> Result<Option<BinaryReader<'a>>> {
+        match self.get_section(section_type) {
+            Some(dir) => {
+                let pos = pe.rva_to_offset(dir.rva)?;
+                Ok(Some(BinaryReader::new(data).sub_reader(pos, dir.size as usize)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn component_assemblies(&self, pe: &WinPe, data: &[u8]) -> Result<Vec<ComponentAssembly>> {
+        let mut reader = match self.section_reader(pe, data, ReadyToRunSectionType::ComponentAssemblies)? {
+            Some(r) => r,
+            None => return Ok(Vec::new()),
+        };
+        let count = reader.raw_data.len() / 16;
+        let entries = reader.repeat(|reader| -> ReadResult<ComponentAssembly> {
+            Ok(ComponentAssembly {
+                cor_header: reader.data_pointer()?,
+                ready_to_run_core_header: reader.data_pointer()?,
+            })
+        }, count as u32)?;
+        Ok(entries)
+    }
+
+    /// native code compiled for the MethodDef with row id `rid`, None if the method has none
+    pub fn method_entry_point(&self, pe: &WinPe, data: &[u8], rid: u32) -> Result<Option<MethodEntryPoint>> {
+        if rid == 0 {
+            return Ok(None);
+        }
+        let mut entry_points = match self.section_reader(pe, data, ReadyToRunSectionType::MethodDefEntryPoints)? {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+        let offset = match native_array_lookup(&entry_points, rid - 1)? {
+            Some(o) => o,
+            None => return Ok(None),
+        };
+
+        entry_points.seek(offset);
+        let id = decode_unsigned(&mut entry_points)?;
+        let (runtime_function_index, has_fixups) = if id & 1 != 0 {
+            (id >> 2, true)
+        } else {
+            (id >> 1, false)
+        };
+
+        let functions = match self.section_reader(pe, data, ReadyToRunSectionType::RuntimeFunctions)? {
+            Some(r) => r,
+            None => return Err(Error::Unsupported("ReadyToRun image without runtime functions")),
+        };
+        //RUNTIME_FUNCTION carries an end address only on x64
+        let entry_size = if pe.native_machine() == Machine::AMD64 { 12 } else { 8 };
+        let begin_rva = functions.sub_reader(runtime_function_index as usize * entry_size, entry_size)?.le_u32()?;
+
+        Ok(Some(MethodEntryPoint {
+            runtime_function_index,
+            begin_rva,
+            has_fixups,
+        }))
+    }
+}
+
+/// NativeFormat variable length unsigned integer
+pub(crate) fn decode_unsigned(reader: &mut BinaryReader) -> Result<u32> {
+    let b = reader.le_u8()? as u32;
+    let value = if b & 1 == 0 {
+        b >> 1
+    } else if b & 2 == 0 {
+        (b >> 2) | (reader.le_u8()? as u32) << 6
+    } else if b & 4 == 0 {
+        (b >> 3) | (reader.le_u8()? as u32) << 5 | (reader.le_u8()? as u32) << 13
+    } else if b & 8 == 0 {
+        (b >> 4) | (reader.le_u8()? as u32) << 4 | (reader.le_u8()? as u32) << 12 | (reader.le_u8()? as u32) << 20
+    } else if b & 16 == 0 {
+        reader.le_u32()?
+    } else {
+        return Err(Error::InvalidValue { what: "native format unsigned", value: b });
+    };
+    Ok(value)
+}
+
+/// offset of element `index` in a NativeFormat sparse array starting at the reader's first byte
+pub(crate) fn native_array_lookup(section: &BinaryReader, index: u32) -> Result<Option<usize>> {
+    const BLOCK_SIZE: u32 = 16;
+
+    let reader = &mut section.sub_reader(0, section.raw_data.len())?;
+    let header = decode_unsigned(reader)?;
+    let base = reader.pos;
+    let count = header >> 2;
+    if index >= count {
+        return Ok(None);
+    }
+
+    let block = (index / BLOCK_SIZE) as usize;
+    let mut offset = base + match header & 3 {
+        0 => section.sub_reader(base + block, 1)?.le_u8()? as usize,
+        1 => section.sub_reader(base + block * 2, 2)?.le_u16()? as usize,
+        _ => section.sub_reader(base + block * 4, 4)?.le_u32()? as usize,
+    };
+
+    let mut bit = BLOCK_SIZE >> 1;
+    while bit > 0 {
+        reader.seek(offset);
+        let val = decode_unsigned(reader)?;
+        if index & bit != 0 {
+            if val & 2 != 0 {
+                offset += (val >> 2) as usize;
+                bit >>= 1;
+                continue;
+            }
+        } else if val & 1 != 0 {
+            offset = reader.pos;
+            bit >>= 1;
+            continue;
+        }
+        //leaf reached before the last level
+        if val & 3 == 0 && (val >> 2) == index & (BLOCK_SIZE - 1) {
+            return Ok(Some(reader.pos));
+        }
+        return Ok(None);
+    }
+    Ok(Some(offset))
+}
+
+/// READYTORUN_HEADER of the image, None for images that carry only IL
+/// or an old NGen header instead
+pub fn parse_ready_to_run(pe: &WinPe, cli: &CLIData, data: &[u8]) -> Result<Option<ReadyToRunHeader>> {
+    if !cli.header.has_managed_native_header() {
+        return Ok(None);
+    }
+    let dir = &cli.header.managed_native_header;
+    let pos = pe.rva_to_offset(dir.rva)?;
+    let reader = &mut BinaryReader::new(data).sub_reader(pos, dir.size as usize)?;
+    if !reader.sub_reader(0, 4)?.tag(b"RTR\0") {
+        return Ok(None);
+    }
+    Ok(Some(ReadyToRunHeader::parse(reader)?))
+}
@@ -1,28 +1,29 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use crate::error::*;
 use crate::il::*;
 use crate::loader::*;
 use crate::reader::BinaryReader;
 use crate::tbl::*;
-use crate::meta::MethodDefSig;
+use crate::meta::{LocalVar, MethodDefSig};
 
 #[derive(Default, Debug)]
-pub struct ReflectionInfo {
-    dll: Rc<RefCell<DllFile>>,
+pub struct ReflectionInfo<'a> {
+    dll: Rc<RefCell<DllFile<'a>>>,
 
     info_class: Vec<Rc<ClassInfo>>,
     info_method: Vec<Rc<MethodInfo>>,
     info_assembly: Vec<Rc<AssemblyInfo>>,
 }
 
-impl ReflectionInfo {
-    pub fn new() -> ReflectionInfo {
+impl<'a> ReflectionInfo<'a> {
+    pub fn new() -> ReflectionInfo<'a> {
         Default::default()
     }
 
 
-    pub fn load_dll(&mut self, dll: &Rc<RefCell<DllFile>>) {
+    pub fn load_dll(&mut self, dll: &Rc<RefCell<DllFile<'a>>>) {
         self.dll = Rc::clone(dll);
     }
 
@@ -30,9 +31,9 @@ impl ReflectionInfo {
         let dll = self.dll.as_ref().borrow();
         let tbl_assembly = &dll.clidata.tbl_assembly;
         let mut index = 0_usize;
-        let asm = tbl_assembly.get_data_by_filter_ind(&(|x| x.name.as_ref() == assembly_name), &mut index);
+        let asm = tbl_assembly.get_data_by_filter_ind(&(|x| dll.string(x.name).map_or(false, |n| n == assembly_name)), &mut index);
         if asm.is_some() {
-            let asm_info = Rc::new(AssemblyInfo::new(&asm.unwrap(), index));
+            let asm_info = Rc::new(AssemblyInfo::new(assembly_name, index));
             let ret = asm_info.clone();
             self.info_assembly.push(asm_info);
             Some(ret)
@@ -41,62 +42,63 @@ impl ReflectionInfo {
         }
     }
 
-    pub fn get_class_info(&mut self, class_name: &str) -> Option<Rc<ClassInfo>> {
+    pub fn get_class_info(&mut self, class_name: &str) -> Result<Option<Rc<ClassInfo>>> {
         let dll = self.dll.as_ref().borrow();
         let clidata = &dll.clidata;
 
         let tbl_typedef = &clidata.tbl_typedef;
 
         let mut index = 0_usize;
-        let typedef = tbl_typedef.get_data_by_filter_ind(&(|x| x.name.as_ref() == class_name), &mut index);
+        let typedef = tbl_typedef.get_data_by_filter_ind(&(|x| dll.string(x.name).map_or(false, |n| n == class_name)), &mut index);
 
         if typedef.is_none() {
-            Option::None
+            Ok(Option::None)
         } else {
             let typedef = typedef.unwrap();
 
-            let method_list_start = typedef.method_list as usize - 1;
-            let total_rows = tbl_typedef.row as usize;
-
-            let mut _method_list_end = method_list_start;
-            if index == total_rows - 1 {
-                _method_list_end = clidata.tbl_methoddef.row as usize;
-            } else {
-                let next_typedef = tbl_typedef.get_data_by_index(index + 1);
-                _method_list_end = next_typedef.method_list as usize;
-            }
-
-            let methods = self.get_method_info_by_index_range(method_list_start, _method_list_end);
+            //MethodPtr indirection is resolved here for uncompressed metadata
+            let method_rids = clidata.type_methods(index as u32 + 1);
+            let methods = self.get_method_info_by_rids(&method_rids)?;
             for item in &methods {
                 self.info_method.push(Rc::clone(&item));
             }
-            let class_info = ClassInfo::new(&typedef, index, methods);
+            let class_info = ClassInfo::new(class_name, dll.string(typedef.namespace)?, index, methods);
 
 
             let rc = Rc::new(class_info);
             self.info_class.push(rc.clone());
-            Some(rc)
+            Ok(Some(rc))
         }
     }
 
-    fn get_method_info_by_index_range(&self, start: usize, end: usize) -> Vec<Rc<MethodInfo>> {
+    fn get_method_info_by_rids(&self, rids: &[u32]) -> Result<Vec<Rc<MethodInfo>>> {
         let dll = self.dll.as_ref().borrow();
         let clidata = &dll.clidata;
         let tbl_method = &clidata.tbl_methoddef;
         let mut vec = Vec::new();
 
-        let mut reader = BinaryReader::new(&dll.data);
+        let mut reader = BinaryReader::new(dll.bytes());
 
-        for ind in start..end {
-            let method = tbl_method.get_data_by_index(ind);
-            let method_sig: MethodDefSig = clidata.parse_signature(&mut reader, method.signature as usize);
-            let addr = clidata.get_rva_addr(method.rva as usize);
-            let method_impl = MethodImpl::parse(&mut reader, addr);
-            let method_info = MethodInfo::new(method, ind, method_impl, method_sig);
+        for &rid in rids {
+            let method = tbl_method.get_row(rid).ok_or(Error::InvalidValue { what: "method row", value: rid })?;
+            let method_sig: MethodDefSig = clidata.parse_signature(dll.bytes(), method.signature)?;
+            //CodeTypeMask, native and runtime methods such as delegate Invoke and abstract
+            //methods have no IL body to interpret
+            let method_impl = if method.impl_flags & 0x3 != 0 || method.rva == 0 {
+                MethodImpl::default()
+            } else {
+                let addr = dll.pe.rva_to_offset(method.rva)?;
+                let mut method_impl = MethodImpl::parse(&mut reader, addr)?;
+                if let Some(sig) = clidata.local_var_sig(dll.bytes(), method_impl.local_var_sig_tok)? {
+                    method_impl.locals = sig.locals;
+                }
+                method_impl
+            };
+            let method_info = MethodInfo::new(method, dll.string(method.name)?, rid as usize - 1, method_impl, method_sig);
             let rc = Rc::new(method_info);
             vec.push(rc);
         }
-        vec
+        Ok(vec)
     }
 
     pub fn get_method_info(&self, method_name: &str, class_info: &Rc<ClassInfo>) -> Option<Rc<MethodInfo>> {
@@ -120,9 +122,9 @@ pub struct AssemblyInfo {
 }
 
 impl AssemblyInfo {
-    pub fn new(meta: &MetaAssembly, index: usize) -> AssemblyInfo {
+    pub fn new(name: &str, index: usize) -> AssemblyInfo {
         AssemblyInfo {
-            name: meta.name.clone(),
+            name: Rc::new(name.to_string()),
             meta_index: index,
         }
     }
@@ -137,10 +139,10 @@ pub struct ClassInfo {
 }
 
 impl ClassInfo {
-    pub fn new(meta: &MetaTypeDef, index: usize, method_list: Vec<Rc<MethodInfo>>) -> ClassInfo {
+    pub fn new(name: &str, namespace: &str, index: usize, method_list: Vec<Rc<MethodInfo>>) -> ClassInfo {
         ClassInfo {
-            name: meta.name.clone(),
-            namespace: meta.namespace.clone(),
+            name: Rc::new(name.to_string()),
+            namespace: Rc::new(namespace.to_string()),
             meta_index: index,
             methods: method_list,
         }
@@ -157,9 +159,9 @@ pub struct MethodInfo {
 }
 
 impl MethodInfo {
-    pub fn new(meta: &MetaMethodDef, index: usize, method_impl: MethodImpl, signature: MethodDefSig) -> MethodInfo {
+    pub fn new(meta: &MetaMethodDef, name: &str, index: usize, method_impl: MethodImpl, signature: MethodDefSig) -> MethodInfo {
         MethodInfo {
-            name: meta.name.clone(),
+            name: Rc::new(name.to_string()),
             rva: meta.rva as usize,
             signature,
             meta_index: index,
@@ -170,31 +172,44 @@ impl MethodInfo {
 
 #[derive(Debug, Default)]
 pub struct MethodImpl {
+    /// false for methods without an IL body, whose instructions are empty
+    pub has_body: bool,
     pub instruction: Vec<Instruction>,
     pub param_list_len: u8,
-
+    pub max_stack: u16,
+    /// StandAloneSig of the locals, nil for tiny headers and methods without locals
+    pub local_var_sig_tok: MetadataToken,
+    /// types of the locals named by local_var_sig_tok
+    pub locals: Vec<LocalVar>,
 }
 
 impl MethodImpl {
-    pub fn parse(reader: &mut BinaryReader, rva: usize) -> MethodImpl {
+    pub fn parse(reader: &mut BinaryReader, rva: usize) -> Result<MethodImpl> {
         reader.seek(rva);
-        let flag = reader.le_u8();
+        let flag = reader.le_u8()?;
         let thin_mode = (flag & 0b11) == 0b10;
         let mut _size = 0_u32;
+        let mut max_stack = 8;
+        let mut local_var_sig_tok = MetadataToken::default();
         if thin_mode {
             _size = (flag >> 2) as u32;
         } else {
-            reader.le_u8();
-            let _max_stack = reader.le_u16();
-            _size = reader.le_u32();
-            let _local_var_sig_toke = reader.le_u32();
+            reader.le_u8()?;
+            max_stack = reader.le_u16()?;
+            _size = reader.le_u32()?;
+            local_var_sig_tok = MetadataToken(reader.le_u32()?);
         }
-        let (instruction_set, param_len) = parse_il_instructions(reader, _size);
+        let body = &mut reader.sub_reader(reader.pos, _size as usize)?;
+        let (instruction_set, param_len) = parse_il_instructions(body, _size)?;
 
-        MethodImpl {
+        Ok(MethodImpl {
+            has_body: true,
             instruction: instruction_set,
             param_list_len: param_len,
-        }
+            max_stack,
+            local_var_sig_tok,
+            locals: Vec::new(),
+        })
     }
 }
 
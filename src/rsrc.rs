@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+
+use crate::error::*;
+use crate::reader::*;
+use crate::winpe::WinPe;
+
+pub const RT_CURSOR: u16 = 1;
+pub const RT_BITMAP: u16 = 2;
+pub const RT_ICON: u16 = 3;
+pub const RT_MENU: u16 = 4;
+pub const RT_DIALOG: u16 = 5;
+pub const RT_STRING: u16 = 6;
+pub const RT_RCDATA: u16 = 10;
+pub const RT_GROUP_ICON: u16 = 14;
+pub const RT_VERSION: u16 = 16;
+pub const RT_MANIFEST: u16 = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceName {
+    Id(u16),
+    Name(String),
+}
+
+/// leaf of the resource tree, one per (type, name, language)
+#[derive(Debug)]
+pub struct ResourceEntry<'a> {
+    pub type_name: ResourceName,
+    pub name: ResourceName,
+    pub language: ResourceName,
+    pub code_page: u32,
+    pub rva: u32,
+    pub data: &'a [u8],
+}
+
+impl<'a> ResourceEntry<'a> {
+    pub fn is_type(&self, id: u16) -> bool {
+        self.type_name == ResourceName::Id(id)
+    }
+}
+
+/// walk type/name/language directories of the .rsrc section and collect every data entry
+pub fn parse_resources<'a>(pe: &WinPe, data: &'a [u8]) -> Result<Vec<ResourceEntry<'a>>> {
+    let dir = &pe.resource_tbl;
+    if dir.rva == 0 || dir.size == 0 {
+        return Ok(Vec::new());
+    }
+    let pos = pe.rva_to_offset(dir.rva)?;
+    let rsrc = BinaryReader::new(data).sub_reader(pos, dir.size as usize)?;
+
+    let walk = &mut DirectoryWalk { visited: HashSet::new(), entries_left: rsrc.raw_data.len() / 8 };
+    let mut entries = Vec::new();
+    for (type_name, type_off) in walk.parse_directory(&rsrc, 0)? {
+        let type_off = subdirectory(type_off)?;
+        for (name, name_off) in walk.parse_directory(&rsrc, type_off)? {
+            let name_off = subdirectory(name_off)?;
+            for (language, data_off) in walk.parse_directory(&rsrc, name_off)? {
+                if data_off & 0x8000_0000 != 0 {
+                    return Err(Error::InvalidValue { what: "resource data entry", value: data_off });
+                }
+                let reader = &mut rsrc.sub_reader(data_off as usize, 16)?;
+                let rva = reader.le_u32()?;
+                let size = reader.le_u32()?;
+                let code_page = reader.le_u32()?;
+                let data_pos = pe.rva_to_offset(rva)?;
+                let data = BinaryReader::new(data).sub_reader(data_pos, size as usize)?.raw_data;
+                entries.push(ResourceEntry {
+                    type_name: type_name.clone(),
+                    name: name.clone(),
+                    language,
+                    code_page,
+                    rva,
+                    data,
+                });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn subdirectory(offset: u32) -> Result<usize> {
+    if offset & 0x8000_0000 == 0 {
+        return Err(Error::InvalidValue { what: "resource subdirectory", value: offset });
+    }
+    Ok((offset & 0x7FFF_FFFF) as usize)
+}
+
+/// directories already read and the entries a well formed tree of this size can still hold,
+/// so crafted offsets that point back into the tree cannot multiply the work
+struct DirectoryWalk {
+    visited: HashSet<usize>,
+    entries_left: usize,
+}
+
+impl DirectoryWalk {
+    /// entries of one IMAGE_RESOURCE_DIRECTORY as (name, offset to data or subdirectory)
+    fn parse_directory(&mut self, rsrc: &BinaryReader, offset: usize) -> Result<Vec<(ResourceName, u32)>> {
+        if !self.visited.insert(offset) {
+            return Err(Error::InvalidValue { what: "resource directory visited twice", value: offset as u32 });
+        }
+        let reader = &mut rsrc.sub_reader(offset, rsrc.raw_data.len().saturating_sub(offset))?;
+        reader.ate(12)?;
+        let named = reader.le_u16()?;
+        let ids = reader.le_u16()?;
+        let count = named as usize + ids as usize;
+        //every entry of a real tree takes its own 8 bytes
+        self.entries_left = self.entries_left.checked_sub(count)
+            .ok_or(Error::InvalidValue { what: "resource directory entry count", value: count as u32 })?;
+
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let name = reader.le_u32()?;
+            let data = reader.le_u32()?;
+            let name = if name & 0x8000_0000 != 0 {
+                let str_pos = (name & 0x7FFF_FFFF) as usize;
+                let len = rsrc.sub_reader(str_pos, 2)?.le_u16()? as usize;
+                ResourceName::Name(utf16(&mut rsrc.sub_reader(str_pos + 2, len * 2)?, len)?)
+            } else {
+                ResourceName::Id(name as u16)
+            };
+            entries.push((name, data));
+        }
+        Ok(entries)
+    }
+}
+
+/// read up to `max` utf-16 units, stopping at NUL
+fn utf16(reader: &mut BinaryReader, max: usize) -> Result<String> {
+    let offset = reader.abs_pos();
+    let mut units = Vec::new();
+    while units.len() < max {
+        let c = reader.le_u16()?;
+        if c == 0 {
+            break;
+        }
+        units.push(c);
+    }
+    String::from_utf16(&units).map_err(|_| Error::InvalidUtf8 { offset })
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FixedFileInfo {
+    pub struc_version: u32,
+    pub file_version_ms: u32,
+    pub file_version_ls: u32,
+    pub product_version_ms: u32,
+    pub product_version_ls: u32,
+    pub file_flags_mask: u32,
+    pub file_flags: u32,
+    pub file_os: u32,
+    pub file_type: u32,
+    pub file_subtype: u32,
+    pub file_date_ms: u32,
+    pub file_date_ls: u32,
+}
+
+impl FixedFileInfo {
+    /// major, minor, build, revision
+    pub fn file_version(&self) -> [u16; 4] {
+        split_version(self.file_version_ms, self.file_version_ls)
+    }
+
+    pub fn product_version(&self) -> [u16; 4] {
+        split_version(self.product_version_ms, self.product_version_ls)
+    }
+
+    fn parse(reader: &mut BinaryReader) -> Result<FixedFileInfo> {
+        let signature = reader.le_u32()?;
+        if signature != 0xFEEF04BD {
+            return Err(Error::InvalidValue { what: "VS_FIXEDFILEINFO signature", value: signature });
+        }
+        Ok(FixedFileInfo {
+            struc_version: reader.le_u32()?,
+            file_version_ms: reader.le_u32()?,
+            file_version_ls: reader.le_u32()?,
+            product_version_ms: reader.le_u32()?,
+            product_version_ls: reader.le_u32()?,
+            file_flags_mask: reader.le_u32()?,
+            file_flags: reader.le_u32()?,
+            file_os: reader.le_u32()?,
+            file_type: reader.le_u32()?,
+            file_subtype: reader.le_u32()?,
+            file_date_ms: reader.le_u32()?,
+            file_date_ls: reader.le_u32()?,
+        })
+    }
+}
+
+fn split_version(ms: u32, ls: u32) -> [u16; 4] {
+    [(ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16]
+}
+
+/// StringTable block, `key` is the language and code page as 8 hex digits
+#[derive(Debug, Default, Clone)]
+pub struct VersionStringTable {
+    pub key: String,
+    pub strings: Vec<(String, String)>,
+}
+
+/// decoded RT_VERSION resource
+#[derive(Debug, Default, Clone)]
+pub struct VersionInfo {
+    pub fixed: Option<FixedFileInfo>,
+    pub string_tables: Vec<VersionStringTable>,
+    /// (language, code page) pairs of VarFileInfo\Translation
+    pub translations: Vec<(u16, u16)>,
+}
+
+impl VersionInfo {
+    /// look up a StringFileInfo value, first table that has it wins
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.string_tables.iter()
+            .flat_map(|t| t.strings.iter())
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn file_version(&self) -> Option<&str> {
+        self.get("FileVersion")
+    }
+
+    pub fn product_version(&self) -> Option<&str> {
+        self.get("ProductVersion")
+    }
+
+    pub fn company_name(&self) -> Option<&str> {
+        self.get("CompanyName")
+    }
+
+    pub fn parse(data: &[u8]) -> Result<VersionInfo> {
+        let root = VersionBlock::parse(&BinaryReader::new(data), 0, 0)?;
+
+        let mut info = VersionInfo::default();
+        if !root.value.raw_data.is_empty() {
+            info.fixed = Some(FixedFileInfo::parse(&mut root.value.sub_reader(0, root.value.raw_data.len())?)?);
+        }
+        for child in &root.children {
+            match child.key.as_str() {
+                "StringFileInfo" => {
+                    for table in &child.children {
+                        let mut strings = Vec::new();
+                        for s in &table.children {
+                            let value = &mut s.value.sub_reader(0, s.value.raw_data.len())?;
+                            strings.push((s.key.clone(), utf16(value, value.raw_data.len() / 2)?));
+                        }
+                        info.string_tables.push(VersionStringTable { key: table.key.clone(), strings });
+                    }
+                }
+                "VarFileInfo" => {
+                    for var in child.children.iter().filter(|v| v.key == "Translation") {
+                        let value = &mut var.value.sub_reader(0, var.value.raw_data.len())?;
+                        while value.remaining() >= 4 {
+                            info.translations.push((value.le_u16()?, value.le_u16()?));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// VS_VERSION_INFO, StringFileInfo or VarFileInfo, StringTable or Var, String
+const MAX_VERSION_DEPTH: u32 = 4;
+
+/// generic version resource block: length, value length, type, key, value, children
+struct VersionBlock<'a> {
+    length: usize,
+    key: String,
+    value: BinaryReader<'a>,
+    children: Vec<VersionBlock<'a>>,
+}
+
+impl<'a> VersionBlock<'a> {
+    /// block at `start` inside `depth` enclosing blocks
+    fn parse(data: &BinaryReader<'a>, start: usize, depth: u32) -> Result<VersionBlock<'a>> {
+        if depth >= MAX_VERSION_DEPTH {
+            return Err(Error::InvalidValue { what: "version block nesting", value: depth });
+        }
+        let header = &mut data.sub_reader(start, 6)?;
+        let length = header.le_u16()? as usize;
+        let value_length = header.le_u16()? as usize;
+        let value_type = header.le_u16()?;
+        if length < 6 {
+            return Err(Error::InvalidValue { what: "version block length", value: length as u32 });
+        }
+        let block = &mut data.sub_reader(start, length)?;
+        block.seek(6);
+
+        let key = utf16(block, length)?;
+        if depth == 0 && key != "VS_VERSION_INFO" {
+            return Err(Error::BadSignature { offset: block.base + 6, expected: b"VS_VERSION_INFO" });
+        }
+        //padding is relative to the start of the resource
+        let base = block.base;
+        let align = |pos: usize| ((base + pos + 3) & !3) - base;
+        block.seek(align(block.pos).min(length));
+
+        //text values count utf-16 units, binary values count bytes
+        let value_bytes = if value_type == 1 { value_length * 2 } else { value_length };
+        let value = block.sub_reader(block.pos, value_bytes.min(block.remaining()))?;
+        block.seek(align(block.pos + value.raw_data.len()).min(length));
+
+        let mut children = Vec::new();
+        while block.remaining() >= 6 {
+            if block.sub_reader(block.pos, 2)?.le_u16()? == 0 {
+                break;
+            }
+            let child = VersionBlock::parse(block, block.pos, depth + 1)?;
+            block.seek(align(block.pos + child.length).min(length));
+            children.push(child);
+        }
+
+        Ok(VersionBlock {
+            length,
+            key,
+            value,
+            children,
+        })
+    }
+}
@@ -0,0 +1,210 @@
+use std::ops::Range;
+
+use num_bigint::BigUint;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::authenticode::{CERTIFICATE_DIRECTORY_INDEX, CHECKSUM_OFFSET};
+use crate::error::*;
+use crate::meta::CLIData;
+use crate::reader::*;
+use crate::winpe::WinPe;
+
+pub const CALG_SHA1: u32 = 0x8004;
+pub const CALG_SHA_256: u32 = 0x800C;
+pub const CALG_SHA_384: u32 = 0x800D;
+pub const CALG_SHA_512: u32 = 0x800E;
+pub const CALG_RSA_SIGN: u32 = 0x2400;
+
+/// placeholder key of framework assemblies, the runtime substitutes the real key
+const ECMA_PUBLIC_KEY: [u8; 16] = [0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StrongNameStatus {
+    Valid,
+    Invalid,
+    /// space for the signature is reserved but the image was never signed
+    DelaySigned,
+    Unsigned,
+}
+
+/// PublicKeyBlob of the Assembly table, wrapping a CAPI RSA PUBLICKEYBLOB
+#[derive(Debug)]
+pub struct StrongNameKey<'a> {
+    pub sig_alg_id: u32,
+    pub hash_alg_id: u32,
+    pub bit_len: u32,
+    pub exponent: u32,
+    /// little endian, as stored in the blob
+    pub modulus: &'a [u8],
+}
+
+impl<'a> StrongNameKey<'a> {
+    pub fn is_ecma_key(blob: &[u8]) -> bool {
+        blob == &ECMA_PUBLIC_KEY[..]
+    }
+
+    pub fn parse(blob: &'a [u8]) -> Result<StrongNameKey<'a>> {
+        if StrongNameKey::is_ecma_key(blob) {
+            return Err(Error::Unsupported("ecma standard public key"));
+        }
+        let reader = &mut BinaryReader::new(blob);
+        let sig_alg_id = reader.le_u32()?;
+        let hash_alg_id = reader.le_u32()?;
+        let key_len = reader.le_u32()?;
+        let key = &mut reader.sub_reader(reader.pos, key_len as usize)?;
+
+        //BLOBHEADER
+        let blob_type = key.le_u8()?;
+        if blob_type != 0x06 {
+            return Err(Error::InvalidValue { what: "public key blob type", value: blob_type as u32 });
+        }
+        key.ate(3)?;
+        let key_alg = key.le_u32()?;
+        if key_alg != CALG_RSA_SIGN {
+            return Err(Error::Unsupported("strong name key algorithm other than RSA"));
+        }
+        //RSAPUBKEY
+        key.expect_tag(b"RSA1")?;
+        let bit_len = key.le_u32()?;
+        let exponent = key.le_u32()?;
+        let modulus = key.bytes(bit_len as usize / 8)?;
+
+        Ok(StrongNameKey {
+            sig_alg_id: if sig_alg_id == 0 { CALG_RSA_SIGN } else { sig_alg_id },
+            hash_alg_id: if hash_alg_id == 0 { CALG_SHA1 } else { hash_alg_id },
+            bit_len,
+            exponent,
+            modulus,
+        })
+    }
+
+    /// RSASSA-PKCS1-v1_5 check of a little endian strong name signature over `hash`
+    pub fn verify(&self, hash: &[u8], signature: &[u8]) -> Result<bool> {
+        let prefix = digest_info_prefix(self.hash_alg_id)?;
+        let k = self.modulus.len();
+        if signature.len() != k || k < prefix.len() + hash.len() + 11 {
+            return Ok(false);
+        }
+
+        let n = BigUint::from_bytes_le(self.modulus);
+        let s = BigUint::from_bytes_le(signature);
+        if s >= n {
+            return Ok(false);
+        }
+        let m = s.modpow(&BigUint::from(self.exponent), &n).to_bytes_be();
+
+        //EM = 00 01 FF..FF 00 DigestInfo
+        let mut expected = vec![0xFF; k];
+        expected[0] = 0x00;
+        expected[1] = 0x01;
+        let info_pos = k - prefix.len() - hash.len();
+        expected[info_pos - 1] = 0x00;
+        expected[info_pos..k - hash.len()].copy_from_slice(prefix);
+        expected[k - hash.len()..].copy_from_slice(hash);
+
+        Ok(m.len() <= k && expected[..k - m.len()].iter().all(|&b| b == 0) && expected[k - m.len()..] == m[..])
+    }
+}
+
+fn digest_info_prefix(hash_alg_id: u32) -> Result<&'static [u8]> {
+    match hash_alg_id {
+        CALG_SHA1 => Ok(&[0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14]),
+        CALG_SHA_256 => Ok(&[0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00, 0x04, 0x20]),
+        CALG_SHA_384 => Ok(&[0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02, 0x05, 0x00, 0x04, 0x30]),
+        CALG_SHA_512 => Ok(&[0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03, 0x05, 0x00, 0x04, 0x40]),
+        _ => Err(Error::InvalidValue { what: "strong name hash algorithm", value: hash_alg_id }),
+    }
+}
+
+/// file range of the strong name signature blob, None when the image reserves no space for it
+fn signature_range(pe: &WinPe, cli: &CLIData) -> Result<Option<Range<usize>>> {
+    let dir = &cli.header.strong_name_signature;
+    if dir.rva == 0 || dir.size == 0 {
+        return Ok(None);
+    }
+    let start = pe.rva_to_offset(dir.rva)?;
+    Ok(Some(start..start + dir.size as usize))
+}
+
+pub fn strong_name_signature<'a>(pe: &WinPe, cli: &CLIData, data: &'a [u8]) -> Result<Option<&'a [u8]>> {
+    match signature_range(pe, cli)? {
+        Some(r) => Ok(Some(BinaryReader::new(data).sub_reader(r.start, r.len())?.raw_data)),
+        None => Ok(None),
+    }
+}
+
+/// strong name hash as the runtime computes it: the headers up to the end of the section table
+/// with the checksum and the certificate table entry zeroed, then the raw data of every section
+/// except the strong name signature blob, padding between them is not covered
+pub fn strong_name_hash(pe: &WinPe, cli: &CLIData, data: &[u8], hash_alg_id: u32) -> Result<Vec<u8>> {
+    let image = BinaryReader::new(data);
+    let headers_end = pe.opt_header_offset + pe.sz_opt_header as usize + pe.num_section as usize * 40;
+    let mut headers = image.sub_reader(0, headers_end)?.raw_data.to_vec();
+    let checksum = pe.opt_header_offset + CHECKSUM_OFFSET;
+    zero(&mut headers, checksum..checksum + 4)?;
+    if pe.num_rva_and_sizes as usize > CERTIFICATE_DIRECTORY_INDEX {
+        let cert_entry = pe.data_directory_offset + CERTIFICATE_DIRECTORY_INDEX * 8;
+        zero(&mut headers, cert_entry..cert_entry + 8)?;
+    }
+
+    let sig = signature_range(pe, cli)?.unwrap_or(0..0);
+    let mut parts: Vec<&[u8]> = vec![&headers];
+    for section in &pe.sections {
+        let raw = image.sub_reader(section.pointer_to_raw_data as usize, section.size_of_raw_data as usize)?;
+        let start = raw.base;
+        let end = start + raw.raw_data.len();
+        parts.push(&data[start..end.min(sig.start).max(start)]);
+        parts.push(&data[start.max(sig.end).min(end)..end]);
+    }
+
+    let hash = match hash_alg_id {
+        CALG_SHA1 => hash_parts::<Sha1>(&parts),
+        CALG_SHA_256 => hash_parts::<Sha256>(&parts),
+        CALG_SHA_384 => hash_parts::<Sha384>(&parts),
+        CALG_SHA_512 => hash_parts::<Sha512>(&parts),
+        _ => return Err(Error::InvalidValue { what: "strong name hash algorithm", value: hash_alg_id }),
+    };
+    Ok(hash)
+}
+
+fn zero(headers: &mut [u8], range: Range<usize>) -> Result<()> {
+    if range.end > headers.len() {
+        return Err(Error::Truncated { offset: range.start });
+    }
+    headers[range].iter_mut().for_each(|b| *b = 0);
+    Ok(())
+}
+
+fn hash_parts<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = D::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+pub fn verify_strong_name(pe: &WinPe, cli: &CLIData, data: &[u8]) -> Result<StrongNameStatus> {
+    let key_blob = match cli.tbl_assembly.data.first() {
+        Some(asm) if asm.public_key != 0 => cli.get_blob(data, asm.public_key)?,
+        _ => &[],
+    };
+    let signature = strong_name_signature(pe, cli, data)?;
+
+    let signature = match (key_blob.is_empty(), signature) {
+        (true, None) | (false, None) => return Ok(StrongNameStatus::Unsigned),
+        (true, Some(_)) => return Ok(StrongNameStatus::Invalid),
+        (false, Some(sig)) => sig,
+    };
+    if !cli.header.is_strong_name_signed() {
+        return Ok(StrongNameStatus::DelaySigned);
+    }
+
+    let key = StrongNameKey::parse(key_blob)?;
+    let hash = strong_name_hash(pe, cli, data, key.hash_alg_id)?;
+    if key.verify(&hash, signature)? {
+        Ok(StrongNameStatus::Valid)
+    } else {
+        Ok(StrongNameStatus::Invalid)
+    }
+}
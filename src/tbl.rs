@@ -1,7 +1,8 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::fmt;
 
+use crate::error::*;
 use crate::reader::*;
 use crate::meta::*;
 
@@ -21,6 +22,8 @@ pub enum CLIColumnType {
     CustomAttributeType = 10,
     ResolutionScope = 11,
     TypeOrMethodDef = 12,
+    /// portable pdb only
+    HasCustomDebugInformation = 13,
 }
 
 lazy_static! {
@@ -45,7 +48,7 @@ lazy_static! {
             CLITableId::InterfaceImpl,
             CLITableId::MemberRef,
             CLITableId::Module,
-
+            CLITableId::DeclSecurity,//Permission
             CLITableId::Property,
             CLITableId::Event,
             CLITableId::StandAloneSig,
@@ -59,7 +62,6 @@ lazy_static! {
             CLITableId::GenericParam,
             CLITableId::GenericParamConstraint,
             CLITableId::MethodSpec,
-            CLITableId::Invalid,//CLITableId::Permission
         ]);
         m.insert(CLIColumnType::HasFieldMarshall,vec![
             CLITableId::Field,
@@ -90,15 +92,15 @@ lazy_static! {
             CLITableId::MethodDef,
         ]);
         m.insert(CLIColumnType::Implementation,vec![
-            CLITableId::Field,
+            CLITableId::File,
             CLITableId::AssemblyRef,
             CLITableId::ExportedType,
         ]);
         m.insert(CLIColumnType::CustomAttributeType,vec![
-            CLITableId::MethodDef,
-            CLITableId::MemberRef,
             CLITableId::Invalid,
             CLITableId::Invalid,
+            CLITableId::MethodDef,
+            CLITableId::MemberRef,
             CLITableId::Invalid,
         ]);
         m.insert(CLIColumnType::ResolutionScope,vec![
@@ -111,11 +113,129 @@ lazy_static! {
             CLITableId::TypeDef,
             CLITableId::MethodDef,
         ]);
+        m.insert(CLIColumnType::HasCustomDebugInformation,vec![
+            CLITableId::MethodDef,
+            CLITableId::Field,
+            CLITableId::TypeRef,
+            CLITableId::TypeDef,
+            CLITableId::Param,
+            CLITableId::InterfaceImpl,
+            CLITableId::MemberRef,
+            CLITableId::Module,
+            CLITableId::DeclSecurity,
+            CLITableId::Property,
+            CLITableId::Event,
+            CLITableId::StandAloneSig,
+            CLITableId::ModuleRef,
+            CLITableId::TypeSpec,
+            CLITableId::Assembly,
+            CLITableId::AssemblyRef,
+            CLITableId::File,
+            CLITableId::ExportedType,
+            CLITableId::ManifestResource,
+            CLITableId::GenericParam,
+            CLITableId::GenericParamConstraint,
+            CLITableId::MethodSpec,
+            CLITableId::Document,
+            CLITableId::LocalScope,
+            CLITableId::LocalVariable,
+            CLITableId::LocalConstant,
+            CLITableId::ImportScope,
+        ]);
         m
     };
 }
 
 
+impl CLIColumnType {
+    /// tables the column can point into, position in the list is the tag
+    pub fn tables(self) -> &'static [CLITableId] {
+        &CLICOLUMN_MAP[&self]
+    }
+
+    /// low bits of the column value that hold the tag
+    pub fn tag_bits(self) -> u32 {
+        let count = self.tables().len() as u32;
+        32 - (count - 1).leading_zeros()
+    }
+
+    /// split a raw column value into table and row, None for a null row or an unused tag
+    pub fn decode(self, value: u32) -> Option<(CLITableId, u32)> {
+        let bits = self.tag_bits();
+        let tag = (value & ((1 << bits) - 1)) as usize;
+        let row = value >> bits;
+        match self.tables().get(tag) {
+            Some(&table) if table != CLITableId::Invalid && row != 0 => Some((table, row)),
+            _ => None,
+        }
+    }
+
+    pub fn encode(self, table: CLITableId, row: u32) -> Option<u32> {
+        let tag = self.tables().iter().position(|&t| t == table && t != CLITableId::Invalid)?;
+        Some(row << self.tag_bits() | tag as u32)
+    }
+}
+
+/// enum over the tables a coded index column may point into, variants carry the row
+macro_rules! coded_index {
+    ($name:ident, $column:ident, [$($table:ident),*]) => {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum $name {
+            $($table(u32),)*
+        }
+
+        impl $name {
+            pub const COLUMN: CLIColumnType = CLIColumnType::$column;
+
+            pub fn decode(value: u32) -> Option<$name> {
+                match Self::COLUMN.decode(value)? {
+                    $((CLITableId::$table, row) => Some($name::$table(row)),)*
+                    _ => None,
+                }
+            }
+
+            pub fn table(&self) -> CLITableId {
+                match *self {
+                    $($name::$table(_) => CLITableId::$table,)*
+                }
+            }
+
+            pub fn row(&self) -> u32 {
+                match *self {
+                    $($name::$table(row) => row,)*
+                }
+            }
+
+            pub fn token(&self) -> (CLITableId, u32) {
+                (self.table(), self.row())
+            }
+
+            pub fn metadata_token(&self) -> MetadataToken {
+                MetadataToken::new(self.table(), self.row())
+            }
+        }
+    };
+}
+
+coded_index!(TypeDefOrRef, TypeDefOrRef, [TypeDef, TypeRef, TypeSpec]);
+coded_index!(HasConstant, HasConstant, [Field, Param, Property]);
+coded_index!(HasCustomAttribute, HasCustomAttribute, [MethodDef, Field, TypeRef, TypeDef, Param, InterfaceImpl, MemberRef, Module,
+    DeclSecurity, Property, Event, StandAloneSig, ModuleRef, TypeSpec, Assembly, AssemblyRef, File, ExportedType, ManifestResource,
+    GenericParam, GenericParamConstraint, MethodSpec]);
+coded_index!(HasFieldMarshal, HasFieldMarshall, [Field, Param]);
+coded_index!(HasDeclSecurity, HasDeclSecurity, [TypeDef, MethodDef, Assembly]);
+coded_index!(MemberRefParent, MemberRefParent, [TypeDef, TypeRef, ModuleRef, MethodDef, TypeSpec]);
+coded_index!(HasSemantics, HasSemantics, [Event, Property]);
+coded_index!(MethodDefOrRef, MethodDefOrRef, [MethodDef, MemberRef]);
+coded_index!(MemberForwarded, MemberForwarded, [Field, MethodDef]);
+coded_index!(Implementation, Implementation, [File, AssemblyRef, ExportedType]);
+coded_index!(CustomAttributeType, CustomAttributeType, [MethodDef, MemberRef]);
+coded_index!(ResolutionScope, ResolutionScope, [Module, ModuleRef, AssemblyRef, TypeRef]);
+coded_index!(TypeOrMethodDef, TypeOrMethodDef, [TypeDef, MethodDef]);
+coded_index!(HasCustomDebugInformation, HasCustomDebugInformation, [MethodDef, Field, TypeRef, TypeDef, Param, InterfaceImpl,
+    MemberRef, Module, DeclSecurity, Property, Event, StandAloneSig, ModuleRef, TypeSpec, Assembly, AssemblyRef, File, ExportedType,
+    ManifestResource, GenericParam, GenericParamConstraint, MethodSpec, Document, LocalScope, LocalVariable, LocalConstant, ImportScope]);
+
 #[derive(Debug, Copy, Clone, Eq)]
 pub enum CLITableId {
     Assembly = 0x20,
@@ -128,12 +248,16 @@ pub enum CLITableId {
     Constant = 0x0B,
     CustomAttribute = 0x0C,
     DeclSecurity = 0x0E,
+    ENCLog = 0x1E,
+    ENCMap = 0x1F,
     EventMap = 0x12,
+    EventPtr = 0x13,
     Event = 0x14,
     ExportedType = 0x27,
     Field = 0x04,
     FieldLayout = 0x10,
     FieldMarshal = 0x0D,
+    FieldPtr = 0x03,
     FieldRVA = 0x1D,
     File = 0x26,
     GenericParam = 0x2A,
@@ -144,18 +268,30 @@ pub enum CLITableId {
     MemberRef = 0x0A,
     MethodDef = 0x06,
     MethodImpl = 0x19,
+    MethodPtr = 0x05,
     MethodSemantics = 0x18,
     MethodSpec = 0x2B,
     Module = 0x00,
     ModuleRef = 0x1A,
     NestedClass = 0x29,
     Param = 0x08,
+    ParamPtr = 0x07,
     Property = 0x17,
     PropertyMap = 0x15,
+    PropertyPtr = 0x16,
     StandAloneSig = 0x11,
     TypeDef = 0x02,
     TypeRef = 0x01,
     TypeSpec = 0x1B,
+    //portable pdb tables
+    Document = 0x30,
+    MethodDebugInformation = 0x31,
+    LocalScope = 0x32,
+    LocalVariable = 0x33,
+    LocalConstant = 0x34,
+    ImportScope = 0x35,
+    StateMachineMethod = 0x36,
+    CustomDebugInformation = 0x37,
     Invalid = 0xFF,
 }
 
@@ -177,9 +313,76 @@ impl PartialEq for CLITableId {
     }
 }
 
+/// table id in the high byte, 1-based row id in the low three bytes
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct MetadataToken(pub u32);
+
+impl MetadataToken {
+    /// table byte of ldstr operands, the row id is an offset into the #US heap
+    pub const USER_STRING: u8 = 0x70;
+
+    pub fn new(table: CLITableId, rid: u32) -> MetadataToken {
+        MetadataToken((table as u32) << 24 | (rid & 0x00FF_FFFF))
+    }
+
+    pub fn table_id(&self) -> u8 {
+        (self.0 >> 24) as u8
+    }
+
+    /// None for user string tokens and unknown tables
+    pub fn table(&self) -> Option<CLITableId> {
+        CLITableId::from_id(self.table_id())
+    }
+
+    pub fn rid(&self) -> u32 {
+        self.0 & 0x00FF_FFFF
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.rid() == 0
+    }
+
+    pub fn is_user_string(&self) -> bool {
+        self.table_id() == MetadataToken::USER_STRING
+    }
+}
+
+impl From<(CLITableId, u32)> for MetadataToken {
+    fn from(token: (CLITableId, u32)) -> Self {
+        MetadataToken::new(token.0, token.1)
+    }
+}
+
+impl fmt::Display for MetadataToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#010x}", self.0)
+    }
+}
+
+impl fmt::Debug for MetadataToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MetadataToken({:#010x})", self.0)
+    }
+}
+
+/// column of a metadata table row, the byte width of every kind but U16 and U32
+/// depends on the heap sizes and row counts of the tilde stream
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CLIColumn {
+    U16,
+    U32,
+    Str,
+    Guid,
+    Blob,
+    /// simple row index into one table
+    Index(CLITableId),
+    /// coded index into one of several tables
+    Coded(CLIColumnType),
+}
+
 impl CLITableId {
-    pub fn map() -> [CLITableId; 38] {
-        static TABLES: [CLITableId; 38] = [
+    pub fn map() -> [CLITableId; 53] {
+        static TABLES: [CLITableId; 53] = [
             CLITableId::Assembly,
             CLITableId::AssemblyOS,
             CLITableId::AssemblyProcessor,
@@ -190,12 +393,16 @@ impl CLITableId {
             CLITableId::Constant,
             CLITableId::CustomAttribute,
             CLITableId::DeclSecurity,
+            CLITableId::ENCLog,
+            CLITableId::ENCMap,
             CLITableId::EventMap,
+            CLITableId::EventPtr,
             CLITableId::Event,
             CLITableId::ExportedType,
             CLITableId::Field,
             CLITableId::FieldLayout,
             CLITableId::FieldMarshal,
+            CLITableId::FieldPtr,
             CLITableId::FieldRVA,
             CLITableId::File,
             CLITableId::GenericParam,
@@ -206,21 +413,100 @@ impl CLITableId {
             CLITableId::MemberRef,
             CLITableId::MethodDef,
             CLITableId::MethodImpl,
+            CLITableId::MethodPtr,
             CLITableId::MethodSemantics,
             CLITableId::MethodSpec,
             CLITableId::Module,
             CLITableId::ModuleRef,
             CLITableId::NestedClass,
             CLITableId::Param,
+            CLITableId::ParamPtr,
             CLITableId::Property,
             CLITableId::PropertyMap,
+            CLITableId::PropertyPtr,
             CLITableId::StandAloneSig,
             CLITableId::TypeDef,
             CLITableId::TypeRef,
             CLITableId::TypeSpec,
+            CLITableId::Document,
+            CLITableId::MethodDebugInformation,
+            CLITableId::LocalScope,
+            CLITableId::LocalVariable,
+            CLITableId::LocalConstant,
+            CLITableId::ImportScope,
+            CLITableId::StateMachineMethod,
+            CLITableId::CustomDebugInformation,
         ];
         TABLES
     }
+
+    pub fn from_id(id: u8) -> Option<CLITableId> {
+        CLITableId::map().iter().find(|&&t| t as u8 == id).copied()
+    }
+
+    /// row layout of the table as given in ECMA-335 II.22
+    pub fn columns(self) -> &'static [CLIColumn] {
+        use CLIColumn::*;
+        use CLIColumnType as C;
+        match self {
+            CLITableId::Module => &[U16, Str, Guid, Guid, Guid],
+            CLITableId::TypeRef => &[Coded(C::ResolutionScope), Str, Str],
+            CLITableId::TypeDef => &[U32, Str, Str, Coded(C::TypeDefOrRef), Index(CLITableId::Field), Index(CLITableId::MethodDef)],
+            CLITableId::FieldPtr => &[Index(CLITableId::Field)],
+            CLITableId::Field => &[U16, Str, Blob],
+            CLITableId::MethodPtr => &[Index(CLITableId::MethodDef)],
+            CLITableId::MethodDef => &[U32, U16, U16, Str, Blob, Index(CLITableId::Param)],
+            CLITableId::ParamPtr => &[Index(CLITableId::Param)],
+            CLITableId::Param => &[U16, U16, Str],
+            CLITableId::InterfaceImpl => &[Index(CLITableId::TypeDef), Coded(C::TypeDefOrRef)],
+            CLITableId::MemberRef => &[Coded(C::MemberRefParent), Str, Blob],
+            //type is one byte followed by a padding byte
+            CLITableId::Constant => &[U16, Coded(C::HasConstant), Blob],
+            CLITableId::CustomAttribute => &[Coded(C::HasCustomAttribute), Coded(C::CustomAttributeType), Blob],
+            CLITableId::FieldMarshal => &[Coded(C::HasFieldMarshall), Blob],
+            CLITableId::DeclSecurity => &[U16, Coded(C::HasDeclSecurity), Blob],
+            CLITableId::ClassLayout => &[U16, U32, Index(CLITableId::TypeDef)],
+            CLITableId::FieldLayout => &[U32, Index(CLITableId::Field)],
+            CLITableId::StandAloneSig => &[Blob],
+            CLITableId::EventMap => &[Index(CLITableId::TypeDef), Index(CLITableId::Event)],
+            CLITableId::EventPtr => &[Index(CLITableId::Event)],
+            CLITableId::Event => &[U16, Str, Coded(C::TypeDefOrRef)],
+            CLITableId::PropertyMap => &[Index(CLITableId::TypeDef), Index(CLITableId::Property)],
+            CLITableId::PropertyPtr => &[Index(CLITableId::Property)],
+            CLITableId::Property => &[U16, Str, Blob],
+            CLITableId::MethodSemantics => &[U16, Index(CLITableId::MethodDef), Coded(C::HasSemantics)],
+            CLITableId::MethodImpl => &[Index(CLITableId::TypeDef), Coded(C::MethodDefOrRef), Coded(C::MethodDefOrRef)],
+            CLITableId::ModuleRef => &[Str],
+            CLITableId::TypeSpec => &[Blob],
+            CLITableId::ImplMap => &[U16, Coded(C::MemberForwarded), Str, Index(CLITableId::ModuleRef)],
+            CLITableId::FieldRVA => &[U32, Index(CLITableId::Field)],
+            CLITableId::ENCLog => &[U32, U32],
+            CLITableId::ENCMap => &[U32],
+            CLITableId::Assembly => &[U32, U16, U16, U16, U16, U32, Blob, Str, Str],
+            CLITableId::AssemblyProcessor => &[U32],
+            CLITableId::AssemblyOS => &[U32, U32, U32],
+            CLITableId::AssemblyRef => &[U16, U16, U16, U16, U32, Blob, Str, Str, Blob],
+            CLITableId::AssemblyRefProcessor => &[U32, Index(CLITableId::AssemblyRef)],
+            CLITableId::AssemblyRefOS => &[U32, U32, U32, Index(CLITableId::AssemblyRef)],
+            CLITableId::File => &[U32, Str, Blob],
+            CLITableId::ExportedType => &[U32, U32, Str, Str, Coded(C::Implementation)],
+            CLITableId::ManifestResource => &[U32, U32, Str, Coded(C::Implementation)],
+            CLITableId::NestedClass => &[Index(CLITableId::TypeDef), Index(CLITableId::TypeDef)],
+            CLITableId::GenericParam => &[U16, U16, Coded(C::TypeOrMethodDef), Str],
+            CLITableId::MethodSpec => &[Coded(C::MethodDefOrRef), Blob],
+            CLITableId::GenericParamConstraint => &[Index(CLITableId::GenericParam), Coded(C::TypeDefOrRef)],
+            CLITableId::Document => &[Blob, Guid, Blob, Guid],
+            CLITableId::MethodDebugInformation => &[Index(CLITableId::Document), Blob],
+            CLITableId::LocalScope => &[Index(CLITableId::MethodDef), Index(CLITableId::ImportScope), Index(CLITableId::LocalVariable),
+                Index(CLITableId::LocalConstant), U32, U32],
+            CLITableId::LocalVariable => &[U16, U16, Str],
+            CLITableId::LocalConstant => &[Str, Blob],
+            CLITableId::ImportScope => &[Index(CLITableId::ImportScope), Blob],
+            CLITableId::StateMachineMethod => &[Index(CLITableId::MethodDef), Index(CLITableId::MethodDef)],
+            CLITableId::CustomDebugInformation => &[Coded(C::HasCustomDebugInformation), Guid, Blob],
+            CLITableId::Invalid => &[],
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -231,6 +517,14 @@ pub struct CLITable<D>
 }
 
 impl<D> CLITable<D> where D: MetaItem<D> {
+    /// row by 1-based row id, as found in tokens and index columns
+    pub fn get_row(&self, rid: u32) -> Option<&D> {
+        if rid == 0 {
+            return None;
+        }
+        self.data.get(rid as usize - 1)
+    }
+
     pub fn get_data_by_index(&self, index: usize) -> &D {
         &self.data[index]
     }
@@ -260,8 +554,27 @@ impl<D> CLITable<D> where D: MetaItem<D> {
 }
 
 
+/// widest row of II.22, Assembly and AssemblyRef have nine columns
+pub const MAX_COLUMNS: usize = 9;
+
+/// column values of one row, heap and row indexes widened to u32
+pub type Row = [u32; MAX_COLUMNS];
+
 pub trait MetaItem<D> {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, string_stream: &CLIStringStream) -> CLITable<D>;
+    const TABLE: CLITableId;
+
+    fn from_row(row: &Row) -> Result<D>;
+
+    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream) -> Result<CLITable<D>> {
+        let row = tilde_stream.get_table_row(Self::TABLE);
+        reader.seek(tilde_stream.get_table_pos(Self::TABLE));
+        let mut data = Vec::new();
+        for _ in 0..row {
+            let values = tilde_stream.read_row(reader, Self::TABLE)?;
+            data.push(Self::from_row(&values)?);
+        }
+        Ok(CLITable::<D> { row, data })
+    }
 }
 
 type StrIndex = u32;
@@ -272,30 +585,24 @@ type RowIndex = u32;
 
 #[derive(Debug, Default)]
 pub struct MetaModule {
-    pub name: Rc<String>,
+    pub generation: u16,
+    pub name: StrIndex,
     pub mvid: GuidIndex,
+    pub enc_id: GuidIndex,
+    pub enc_base_id: GuidIndex,
 }
 
 impl MetaItem<MetaModule> for MetaModule {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, string_stream: &CLIStringStream) -> CLITable<MetaModule> {
-        let row = tilde_stream.get_table_row(CLITableId::Module);
-        let mut data: Vec<MetaModule> = Vec::new();
-        let heap_size = tilde_stream.heap_size;
-        for _ in 0..row {
-            reader.ate(2);
-            let item_name = reader.le_uint(heap_size.string);
-            let item_mvid = reader.le_uint(heap_size.guid);
-            reader.le_uint(heap_size.guid);
-            reader.le_uint(heap_size.guid);
-            data.push(MetaModule {
-                name: string_stream.get_str_by_index(item_name),
-                mvid: item_mvid,
-            });
-        };
-        CLITable::<MetaModule> {
-            row,
-            data,
-        }
+    const TABLE: CLITableId = CLITableId::Module;
+
+    fn from_row(row: &Row) -> Result<MetaModule> {
+        Ok(MetaModule {
+            generation: row[0] as u16,
+            name: row[1],
+            mvid: row[2],
+            enc_id: row[3],
+            enc_base_id: row[4],
+        })
     }
 }
 
@@ -303,27 +610,26 @@ impl MetaItem<MetaModule> for MetaModule {
 pub struct MetaTypeRef {
     pub resolution_scope: TagIndex,
     //ResolutionScope
-    pub name: Rc<String>,
-    pub namespace: Rc<String>,
+    pub name: StrIndex,
+    pub namespace: StrIndex,
+}
+
+impl MetaTypeRef {
+    /// None for a TypeRef resolved through ExportedType
+    pub fn scope(&self) -> Option<ResolutionScope> {
+        ResolutionScope::decode(self.resolution_scope)
+    }
 }
 
 impl MetaItem<MetaTypeRef> for MetaTypeRef {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, string_stream: &CLIStringStream) -> CLITable<MetaTypeRef> {
-        let row = tilde_stream.get_table_row(CLITableId::TypeRef);
-        let heap_size = tilde_stream.heap_size;
-        let column_size = tilde_stream.get_column_byte(CLIColumnType::ResolutionScope);
-        let mut data = Vec::new();
-        for _ in 0..row {
-            let scope = reader.le_uint(column_size);
-            let name = reader.le_uint(heap_size.string);
-            let namespace = reader.le_uint(heap_size.string);
-            data.push(MetaTypeRef {
-                resolution_scope: scope,
-                name: string_stream.get_str_by_index(name),
-                namespace: string_stream.get_str_by_index(namespace),
-            });
-        };
-        CLITable::<MetaTypeRef> { row, data }
+    const TABLE: CLITableId = CLITableId::TypeRef;
+
+    fn from_row(row: &Row) -> Result<MetaTypeRef> {
+        Ok(MetaTypeRef {
+            resolution_scope: row[0],
+            name: row[1],
+            namespace: row[2],
+        })
     }
 }
 
@@ -331,8 +637,8 @@ impl MetaItem<MetaTypeRef> for MetaTypeRef {
 pub struct MetaTypeDef {
     pub type_attribute: TagIndex,
     //TypeAttribute 4byte
-    pub name: Rc<String>,
-    pub namespace: Rc<String>,
+    pub name: StrIndex,
+    pub namespace: StrIndex,
     pub extends: TagIndex,
     //TypeDefOrRef
     pub field_list: RowIndex,
@@ -340,33 +646,25 @@ pub struct MetaTypeDef {
     pub method_list: RowIndex,//MethodDef table TODO
 }
 
-impl MetaItem<MetaTypeDef> for MetaTypeDef {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, string_stream: &CLIStringStream) -> CLITable<MetaTypeDef> {
-        let row = tilde_stream.get_table_row(CLITableId::TypeDef);
-        let heap_size = tilde_stream.heap_size;
-        let byte_extends = tilde_stream.get_column_byte(CLIColumnType::TypeDefOrRef);
+impl MetaTypeDef {
+    /// None for interfaces and System.Object
+    pub fn base_type(&self) -> Option<TypeDefOrRef> {
+        TypeDefOrRef::decode(self.extends)
+    }
+}
 
-        let mut data = Vec::new();
-        for _ in 0..row {
-            let type_attr = reader.le_u32();
-            let name = reader.le_uint(heap_size.string);
-            let namespace = reader.le_uint(heap_size.string);
-            let extends = reader.le_uint(byte_extends);
-            let field_list = reader.le_u16() as u32;
-            let method_list = reader.le_u16() as u32;
-            data.push(MetaTypeDef {
-                type_attribute: type_attr,
-                name: string_stream.get_str_by_index(name),
-                namespace: string_stream.get_str_by_index(namespace),
-                extends,
-                field_list,
-                method_list,
-            });
-        };
-        CLITable::<MetaTypeDef> {
-            row,
-            data,
-        }
+impl MetaItem<MetaTypeDef> for MetaTypeDef {
+    const TABLE: CLITableId = CLITableId::TypeDef;
+
+    fn from_row(row: &Row) -> Result<MetaTypeDef> {
+        Ok(MetaTypeDef {
+            type_attribute: row[0],
+            name: row[1],
+            namespace: row[2],
+            extends: row[3],
+            field_list: row[4],
+            method_list: row[5],
+        })
     }
 }
 
@@ -377,6 +675,17 @@ pub struct MetaPropertyMap {
     pub property_list: RowIndex, // Property table TODO
 }
 
+impl MetaItem<MetaPropertyMap> for MetaPropertyMap {
+    const TABLE: CLITableId = CLITableId::PropertyMap;
+
+    fn from_row(row: &Row) -> Result<MetaPropertyMap> {
+        Ok(MetaPropertyMap {
+            parent: row[0],
+            property_list: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaProperty {
     pub flags: u16,
@@ -385,6 +694,18 @@ pub struct MetaProperty {
     pub type_data: BlobIndex,
 }
 
+impl MetaItem<MetaProperty> for MetaProperty {
+    const TABLE: CLITableId = CLITableId::Property;
+
+    fn from_row(row: &Row) -> Result<MetaProperty> {
+        Ok(MetaProperty {
+            flags: row[0] as u16,
+            name: row[1],
+            type_data: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaParam {
     pub flags: u16,
@@ -393,6 +714,18 @@ pub struct MetaParam {
     pub name: StrIndex,
 }
 
+impl MetaItem<MetaParam> for MetaParam {
+    const TABLE: CLITableId = CLITableId::Param;
+
+    fn from_row(row: &Row) -> Result<MetaParam> {
+        Ok(MetaParam {
+            flags: row[0] as u16,
+            sequence: row[1] as u16,
+            name: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaNestedClass {
     pub nested_class: RowIndex,
@@ -400,11 +733,43 @@ pub struct MetaNestedClass {
     pub enclosing_class: RowIndex, //TypeDef table
 }
 
+impl MetaItem<MetaNestedClass> for MetaNestedClass {
+    const TABLE: CLITableId = CLITableId::NestedClass;
+
+    fn from_row(row: &Row) -> Result<MetaNestedClass> {
+        Ok(MetaNestedClass {
+            nested_class: row[0],
+            enclosing_class: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaModuleRef {
     pub name: StrIndex,
 }
 
+impl MetaItem<MetaModuleRef> for MetaModuleRef {
+    const TABLE: CLITableId = CLITableId::ModuleRef;
+
+    fn from_row(row: &Row) -> Result<MetaModuleRef> {
+        Ok(MetaModuleRef { name: row[0] })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MetaTypeSpec {
+    pub signature: BlobIndex,
+}
+
+impl MetaItem<MetaTypeSpec> for MetaTypeSpec {
+    const TABLE: CLITableId = CLITableId::TypeSpec;
+
+    fn from_row(row: &Row) -> Result<MetaTypeSpec> {
+        Ok(MetaTypeSpec { signature: row[0] })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaMethodSpec {
     pub method: TagIndex,
@@ -412,6 +777,23 @@ pub struct MetaMethodSpec {
     pub instantiation: BlobIndex,
 }
 
+impl MetaMethodSpec {
+    pub fn method_def_or_ref(&self) -> Option<MethodDefOrRef> {
+        MethodDefOrRef::decode(self.method)
+    }
+}
+
+impl MetaItem<MetaMethodSpec> for MetaMethodSpec {
+    const TABLE: CLITableId = CLITableId::MethodSpec;
+
+    fn from_row(row: &Row) -> Result<MetaMethodSpec> {
+        Ok(MetaMethodSpec {
+            method: row[0],
+            instantiation: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaMethodSemantics {
     pub semantics: u16,
@@ -421,6 +803,18 @@ pub struct MetaMethodSemantics {
     pub association: TagIndex,// HasSemantics column
 }
 
+impl MetaItem<MetaMethodSemantics> for MetaMethodSemantics {
+    const TABLE: CLITableId = CLITableId::MethodSemantics;
+
+    fn from_row(row: &Row) -> Result<MetaMethodSemantics> {
+        Ok(MetaMethodSemantics {
+            semantics: row[0] as u16,
+            method: row[1],
+            association: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaMethodImpl {
     pub class: RowIndex,
@@ -430,6 +824,18 @@ pub struct MetaMethodImpl {
     pub method_decl: TagIndex,// MethodDefOrRef,
 }
 
+impl MetaItem<MetaMethodImpl> for MetaMethodImpl {
+    const TABLE: CLITableId = CLITableId::MethodImpl;
+
+    fn from_row(row: &Row) -> Result<MetaMethodImpl> {
+        Ok(MetaMethodImpl {
+            class: row[0],
+            method_body: row[1],
+            method_decl: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaMethodDef {
     pub rva: u32,
@@ -437,35 +843,23 @@ pub struct MetaMethodDef {
     //MethodImplAttributes
     pub flags: u16,
     //MethodAttributes,
-    pub name: Rc<String>,
+    pub name: StrIndex,
     pub signature: BlobIndex,
     pub param_list: RowIndex,//Param table TODO
 }
 
 impl MetaItem<MetaMethodDef> for MetaMethodDef {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, string_stream: &CLIStringStream) -> CLITable<MetaMethodDef> {
-        let row = tilde_stream.get_table_row(CLITableId::MethodDef);
-        let _heap_size = tilde_stream.heap_size;
-        let mut data = Vec::new();
-        for _ in 0..row {
-            let rva = reader.le_u32();
-            let impl_flags = reader.le_u16();
-            let flags = reader.le_u16();
-            let name = reader.le_u16() as u32;
-            let signature = reader.le_u16() as u32;
-            let param_list = reader.le_u16() as u32;
-
-
-            data.push(MetaMethodDef {
-                rva,
-                impl_flags,
-                flags,
-                name: string_stream.get_str_by_index(name),
-                signature,
-                param_list,
-            });
-        };
-        CLITable::<MetaMethodDef> { row, data }
+    const TABLE: CLITableId = CLITableId::MethodDef;
+
+    fn from_row(row: &Row) -> Result<MetaMethodDef> {
+        Ok(MetaMethodDef {
+            rva: row[0],
+            impl_flags: row[1] as u16,
+            flags: row[2] as u16,
+            name: row[3],
+            signature: row[4],
+            param_list: row[5],
+        })
     }
 }
 
@@ -473,28 +867,25 @@ impl MetaItem<MetaMethodDef> for MetaMethodDef {
 pub struct MetaMemberRef {
     pub class: TagIndex,
     //MemberRefParent
-    pub name: Rc<String>,
+    pub name: StrIndex,
     pub signature: BlobIndex,
 }
 
-impl MetaItem<MetaMemberRef> for MetaMemberRef {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, string_stream: &CLIStringStream) -> CLITable<MetaMemberRef> {
-        let row = tilde_stream.get_table_row(CLITableId::MemberRef);
-        let heap_size = tilde_stream.heap_size;
-        let column_class = tilde_stream.get_column_byte(CLIColumnType::MemberRefParent);
+impl MetaMemberRef {
+    pub fn parent(&self) -> Option<MemberRefParent> {
+        MemberRefParent::decode(self.class)
+    }
+}
 
-        let mut data = Vec::new();
-        for _ in 0..row {
-            let class = reader.le_uint(column_class);
-            let name = reader.le_uint(heap_size.string);
-            let signature = reader.le_uint(heap_size.blob);
-            data.push(MetaMemberRef {
-                class,
-                name: string_stream.get_str_by_index(name),
-                signature,
-            });
-        };
-        CLITable::<MetaMemberRef> { row, data }
+impl MetaItem<MetaMemberRef> for MetaMemberRef {
+    const TABLE: CLITableId = CLITableId::MemberRef;
+
+    fn from_row(row: &Row) -> Result<MetaMemberRef> {
+        Ok(MetaMemberRef {
+            class: row[0],
+            name: row[1],
+            signature: row[2],
+        })
     }
 }
 
@@ -507,6 +898,19 @@ pub struct MetaManifestResource {
     pub implementation: TagIndex,//Implementation,
 }
 
+impl MetaItem<MetaManifestResource> for MetaManifestResource {
+    const TABLE: CLITableId = CLITableId::ManifestResource;
+
+    fn from_row(row: &Row) -> Result<MetaManifestResource> {
+        Ok(MetaManifestResource {
+            offset: row[0],
+            flags: row[1],
+            name: row[2],
+            implementation: row[3],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaInterfaceImpl {
     pub class: RowIndex,
@@ -514,16 +918,40 @@ pub struct MetaInterfaceImpl {
     pub interface: TagIndex, //TypeDefOrRef
 }
 
+impl MetaItem<MetaInterfaceImpl> for MetaInterfaceImpl {
+    const TABLE: CLITableId = CLITableId::InterfaceImpl;
+
+    fn from_row(row: &Row) -> Result<MetaInterfaceImpl> {
+        Ok(MetaInterfaceImpl {
+            class: row[0],
+            interface: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaImplMap {
     pub mapping_flags: u16,
     //PInvokeAttribute,
-    pub member_forwarded: RowIndex,
-    // MethodDef table,
+    pub member_forwarded: TagIndex,
+    // MemberForwarded, only ever a MethodDef
     pub import_name: StrIndex,
     pub import_scope: RowIndex,//ModuleRef
 }
 
+impl MetaItem<MetaImplMap> for MetaImplMap {
+    const TABLE: CLITableId = CLITableId::ImplMap;
+
+    fn from_row(row: &Row) -> Result<MetaImplMap> {
+        Ok(MetaImplMap {
+            mapping_flags: row[0] as u16,
+            member_forwarded: row[1],
+            import_name: row[2],
+            import_scope: row[3],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaGenericParamConstraint {
     pub owner: RowIndex,
@@ -531,6 +959,17 @@ pub struct MetaGenericParamConstraint {
     pub constraint: TagIndex,// TypeDefOrRef
 }
 
+impl MetaItem<MetaGenericParamConstraint> for MetaGenericParamConstraint {
+    const TABLE: CLITableId = CLITableId::GenericParamConstraint;
+
+    fn from_row(row: &Row) -> Result<MetaGenericParamConstraint> {
+        Ok(MetaGenericParamConstraint {
+            owner: row[0],
+            constraint: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaGenericParam {
     pub number: RowIndex,
@@ -542,6 +981,19 @@ pub struct MetaGenericParam {
     pub name: StrIndex,
 }
 
+impl MetaItem<MetaGenericParam> for MetaGenericParam {
+    const TABLE: CLITableId = CLITableId::GenericParam;
+
+    fn from_row(row: &Row) -> Result<MetaGenericParam> {
+        Ok(MetaGenericParam {
+            number: row[0],
+            flags: row[1] as u16,
+            owner: row[2],
+            name: row[3],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaFile {
     pub flags: u32,
@@ -550,12 +1002,35 @@ pub struct MetaFile {
     pub hash_value: BlobIndex,
 }
 
+impl MetaItem<MetaFile> for MetaFile {
+    const TABLE: CLITableId = CLITableId::File;
+
+    fn from_row(row: &Row) -> Result<MetaFile> {
+        Ok(MetaFile {
+            flags: row[0],
+            name: row[1],
+            hash_value: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaFieldRVA {
     pub rva: u32,
     pub field: RowIndex, //Field table
 }
 
+impl MetaItem<MetaFieldRVA> for MetaFieldRVA {
+    const TABLE: CLITableId = CLITableId::FieldRVA;
+
+    fn from_row(row: &Row) -> Result<MetaFieldRVA> {
+        Ok(MetaFieldRVA {
+            rva: row[0],
+            field: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaFieldMarshal {
     pub parent: TagIndex,
@@ -563,12 +1038,34 @@ pub struct MetaFieldMarshal {
     pub native_type: BlobIndex,
 }
 
+impl MetaItem<MetaFieldMarshal> for MetaFieldMarshal {
+    const TABLE: CLITableId = CLITableId::FieldMarshal;
+
+    fn from_row(row: &Row) -> Result<MetaFieldMarshal> {
+        Ok(MetaFieldMarshal {
+            parent: row[0],
+            native_type: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaFieldLayout {
     pub offset: u32,
     pub field: RowIndex,// Field table,
 }
 
+impl MetaItem<MetaFieldLayout> for MetaFieldLayout {
+    const TABLE: CLITableId = CLITableId::FieldLayout;
+
+    fn from_row(row: &Row) -> Result<MetaFieldLayout> {
+        Ok(MetaFieldLayout {
+            offset: row[0],
+            field: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaField {
     pub flags: u16,
@@ -577,6 +1074,18 @@ pub struct MetaField {
     pub signature: BlobIndex,
 }
 
+impl MetaItem<MetaField> for MetaField {
+    const TABLE: CLITableId = CLITableId::Field;
+
+    fn from_row(row: &Row) -> Result<MetaField> {
+        Ok(MetaField {
+            flags: row[0] as u16,
+            name: row[1],
+            signature: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaExportedType {
     pub flags: u32,
@@ -588,6 +1097,20 @@ pub struct MetaExportedType {
     pub implementation: TagIndex,//Implementation column,
 }
 
+impl MetaItem<MetaExportedType> for MetaExportedType {
+    const TABLE: CLITableId = CLITableId::ExportedType;
+
+    fn from_row(row: &Row) -> Result<MetaExportedType> {
+        Ok(MetaExportedType {
+            flags: row[0],
+            type_def_id: row[1],
+            type_name: row[2],
+            type_namespace: row[3],
+            implementation: row[4],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaEvent {
     pub event_flags: u16,
@@ -596,6 +1119,18 @@ pub struct MetaEvent {
     pub event_type: TagIndex,//TypeDefOrRef,
 }
 
+impl MetaItem<MetaEvent> for MetaEvent {
+    const TABLE: CLITableId = CLITableId::Event;
+
+    fn from_row(row: &Row) -> Result<MetaEvent> {
+        Ok(MetaEvent {
+            event_flags: row[0] as u16,
+            name: row[1],
+            event_type: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaEventMap {
     pub parent: RowIndex,
@@ -603,6 +1138,17 @@ pub struct MetaEventMap {
     pub event_list: RowIndex,//Event table TODO
 }
 
+impl MetaItem<MetaEventMap> for MetaEventMap {
+    const TABLE: CLITableId = CLITableId::EventMap;
+
+    fn from_row(row: &Row) -> Result<MetaEventMap> {
+        Ok(MetaEventMap {
+            parent: row[0],
+            event_list: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaDeclSecurity {
     pub action: u16,
@@ -611,6 +1157,18 @@ pub struct MetaDeclSecurity {
     pub permission_set: BlobIndex,
 }
 
+impl MetaItem<MetaDeclSecurity> for MetaDeclSecurity {
+    const TABLE: CLITableId = CLITableId::DeclSecurity;
+
+    fn from_row(row: &Row) -> Result<MetaDeclSecurity> {
+        Ok(MetaDeclSecurity {
+            action: row[0] as u16,
+            parent: row[1],
+            permission_set: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaCustomAttribute {
     pub parent: TagIndex,
@@ -620,27 +1178,26 @@ pub struct MetaCustomAttribute {
     pub value: BlobIndex,
 }
 
-impl MetaItem<MetaCustomAttribute> for MetaCustomAttribute {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, _string_stream: &CLIStringStream) -> CLITable<MetaCustomAttribute> {
-        let row = tilde_stream.get_table_row(CLITableId::CustomAttribute);
-        let heap_size = tilde_stream.heap_size;
-        let column_parent = tilde_stream.get_column_byte(CLIColumnType::HasCustomAttribute);
-        let column_attr_type = tilde_stream.get_column_byte(CLIColumnType::CustomAttributeType);
-
-        let mut data = Vec::new();
+impl MetaCustomAttribute {
+    pub fn owner(&self) -> Option<HasCustomAttribute> {
+        HasCustomAttribute::decode(self.parent)
+    }
 
-        for _ in 0..row {
-            let parent = reader.le_uint(column_parent);
-            let attr_type = reader.le_uint(column_attr_type);
-            let value = reader.le_uint(heap_size.blob);
-            data.push(MetaCustomAttribute {
-                parent,
-                attr_type,
-                value,
-            })
-        }
+    /// the attribute's .ctor
+    pub fn constructor(&self) -> Option<CustomAttributeType> {
+        CustomAttributeType::decode(self.attr_type)
+    }
+}
 
-        CLITable::<MetaCustomAttribute> { row, data }
+impl MetaItem<MetaCustomAttribute> for MetaCustomAttribute {
+    const TABLE: CLITableId = CLITableId::CustomAttribute;
+
+    fn from_row(row: &Row) -> Result<MetaCustomAttribute> {
+        Ok(MetaCustomAttribute {
+            parent: row[0],
+            attr_type: row[1],
+            value: row[2],
+        })
     }
 }
 
@@ -653,6 +1210,18 @@ pub struct MetaConstant {
     pub value: BlobIndex,
 }
 
+impl MetaItem<MetaConstant> for MetaConstant {
+    const TABLE: CLITableId = CLITableId::Constant;
+
+    fn from_row(row: &Row) -> Result<MetaConstant> {
+        Ok(MetaConstant {
+            const_type: row[0] as u8,
+            parent: row[1],
+            value: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaClassLayout {
     pub packing_size: u16,
@@ -660,12 +1229,35 @@ pub struct MetaClassLayout {
     pub parent: RowIndex,//TypeDef table
 }
 
+impl MetaItem<MetaClassLayout> for MetaClassLayout {
+    const TABLE: CLITableId = CLITableId::ClassLayout;
+
+    fn from_row(row: &Row) -> Result<MetaClassLayout> {
+        Ok(MetaClassLayout {
+            packing_size: row[0] as u16,
+            class_size: row[1],
+            parent: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaAssemblyRefProcessor {
     pub processor: u32,
     pub assembly_ref: RowIndex,//AssemblyRef,
 }
 
+impl MetaItem<MetaAssemblyRefProcessor> for MetaAssemblyRefProcessor {
+    const TABLE: CLITableId = CLITableId::AssemblyRefProcessor;
+
+    fn from_row(row: &Row) -> Result<MetaAssemblyRefProcessor> {
+        Ok(MetaAssemblyRefProcessor {
+            processor: row[0],
+            assembly_ref: row[1],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaAssemblyRefOS {
     pub platform_id: u32,
@@ -674,6 +1266,19 @@ pub struct MetaAssemblyRefOS {
     pub asssmbly_ref: RowIndex,//AssemblyRef table
 }
 
+impl MetaItem<MetaAssemblyRefOS> for MetaAssemblyRefOS {
+    const TABLE: CLITableId = CLITableId::AssemblyRefOS;
+
+    fn from_row(row: &Row) -> Result<MetaAssemblyRefOS> {
+        Ok(MetaAssemblyRefOS {
+            platform_id: row[0],
+            major_ver: row[1],
+            minor_ver: row[2],
+            asssmbly_ref: row[3],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaAssemblyRef {
     pub maj_ver: u16,
@@ -682,40 +1287,27 @@ pub struct MetaAssemblyRef {
     pub revision_num: u16,
     pub flags: u32,
     //AssemblyFlags
-//    pub public_key_or_token:u32,
-    pub name: Rc<String>,
-    pub culture: Rc<String>,
+    pub public_key_or_token: BlobIndex,
+    pub name: StrIndex,
+    pub culture: StrIndex,
     pub hash_value: BlobIndex,
 }
 
 impl MetaItem<MetaAssemblyRef> for MetaAssemblyRef {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, string_stream: &CLIStringStream) -> CLITable<MetaAssemblyRef> {
-        let row = tilde_stream.get_table_row(CLITableId::AssemblyRef);
-        let heap_size = tilde_stream.heap_size;
-
-        let mut data = Vec::new();
-        for _ in 0..row {
-            let maj_ver = reader.le_u16();
-            let min_ver = reader.le_u16();
-            let build_num = reader.le_u16();
-            let revision_num = reader.le_u16();
-            let flags = reader.le_u32();
-            reader.le_uint(heap_size.blob);
-            let name = reader.le_uint(heap_size.string);
-            let culture = reader.le_uint(heap_size.string);
-            let hash_value = reader.le_uint(heap_size.blob);
-            data.push(MetaAssemblyRef {
-                maj_ver,
-                min_ver,
-                build_num,
-                revision_num,
-                flags,
-                name: string_stream.get_str_by_index(name),
-                culture: string_stream.get_str_by_index(culture),
-                hash_value,
-            });
-        }
-        CLITable::<MetaAssemblyRef> { row, data }
+    const TABLE: CLITableId = CLITableId::AssemblyRef;
+
+    fn from_row(row: &Row) -> Result<MetaAssemblyRef> {
+        Ok(MetaAssemblyRef {
+            maj_ver: row[0] as u16,
+            min_ver: row[1] as u16,
+            build_num: row[2] as u16,
+            revision_num: row[3] as u16,
+            flags: row[4],
+            public_key_or_token: row[5],
+            name: row[6],
+            culture: row[7],
+            hash_value: row[8],
+        })
     }
 }
 
@@ -724,6 +1316,14 @@ pub struct MetaAssemblyProcessor {
     pub processor: u32,
 }
 
+impl MetaItem<MetaAssemblyProcessor> for MetaAssemblyProcessor {
+    const TABLE: CLITableId = CLITableId::AssemblyProcessor;
+
+    fn from_row(row: &Row) -> Result<MetaAssemblyProcessor> {
+        Ok(MetaAssemblyProcessor { processor: row[0] })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaAssemblyOS {
     pub platform_id: u32,
@@ -731,21 +1331,130 @@ pub struct MetaAssemblyOS {
     pub minor_ver: u32,
 }
 
+impl MetaItem<MetaAssemblyOS> for MetaAssemblyOS {
+    const TABLE: CLITableId = CLITableId::AssemblyOS;
+
+    fn from_row(row: &Row) -> Result<MetaAssemblyOS> {
+        Ok(MetaAssemblyOS {
+            platform_id: row[0],
+            major_ver: row[1],
+            minor_ver: row[2],
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MetaStandAloneSig {
     pub signature: u32,
 }
 
 impl MetaItem<MetaStandAloneSig> for MetaStandAloneSig {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, _string_stream: &CLIStringStream) -> CLITable<MetaStandAloneSig> {
-        let row = tilde_stream.get_table_row(CLITableId::StandAloneSig);
-        let heap_size = tilde_stream.heap_size;
-        let mut data = Vec::new();
-        for _ in 0..row {
-            let signature = reader.le_uint(heap_size.blob);
-            data.push(MetaStandAloneSig { signature });
-        }
-        CLITable::<MetaStandAloneSig> { row, data }
+    const TABLE: CLITableId = CLITableId::StandAloneSig;
+
+    fn from_row(row: &Row) -> Result<MetaStandAloneSig> {
+        Ok(MetaStandAloneSig { signature: row[0] })
+    }
+}
+
+/// Field row at this position of a field list, only in uncompressed #- metadata
+#[derive(Debug, Default)]
+pub struct MetaFieldPtr {
+    pub field: RowIndex,
+}
+
+impl MetaItem<MetaFieldPtr> for MetaFieldPtr {
+    const TABLE: CLITableId = CLITableId::FieldPtr;
+
+    fn from_row(row: &Row) -> Result<MetaFieldPtr> {
+        Ok(MetaFieldPtr { field: row[0] })
+    }
+}
+
+/// MethodDef row at this position of a method list, only in uncompressed #- metadata
+#[derive(Debug, Default)]
+pub struct MetaMethodPtr {
+    pub method: RowIndex,
+}
+
+impl MetaItem<MetaMethodPtr> for MetaMethodPtr {
+    const TABLE: CLITableId = CLITableId::MethodPtr;
+
+    fn from_row(row: &Row) -> Result<MetaMethodPtr> {
+        Ok(MetaMethodPtr { method: row[0] })
+    }
+}
+
+/// Param row at this position of a param list, only in uncompressed #- metadata
+#[derive(Debug, Default)]
+pub struct MetaParamPtr {
+    pub param: RowIndex,
+}
+
+impl MetaItem<MetaParamPtr> for MetaParamPtr {
+    const TABLE: CLITableId = CLITableId::ParamPtr;
+
+    fn from_row(row: &Row) -> Result<MetaParamPtr> {
+        Ok(MetaParamPtr { param: row[0] })
+    }
+}
+
+/// Event row at this position of an event list, only in uncompressed #- metadata
+#[derive(Debug, Default)]
+pub struct MetaEventPtr {
+    pub event: RowIndex,
+}
+
+impl MetaItem<MetaEventPtr> for MetaEventPtr {
+    const TABLE: CLITableId = CLITableId::EventPtr;
+
+    fn from_row(row: &Row) -> Result<MetaEventPtr> {
+        Ok(MetaEventPtr { event: row[0] })
+    }
+}
+
+/// Property row at this position of a property list, only in uncompressed #- metadata
+#[derive(Debug, Default)]
+pub struct MetaPropertyPtr {
+    pub property: RowIndex,
+}
+
+impl MetaItem<MetaPropertyPtr> for MetaPropertyPtr {
+    const TABLE: CLITableId = CLITableId::PropertyPtr;
+
+    fn from_row(row: &Row) -> Result<MetaPropertyPtr> {
+        Ok(MetaPropertyPtr { property: row[0] })
+    }
+}
+
+/// edit and continue record of a delta, func_code says what the edit did to the token's row
+#[derive(Debug, Default)]
+pub struct MetaENCLog {
+    pub token: MetadataToken,
+    pub func_code: u32,
+}
+
+impl MetaItem<MetaENCLog> for MetaENCLog {
+    const TABLE: CLITableId = CLITableId::ENCLog;
+
+    fn from_row(row: &Row) -> Result<MetaENCLog> {
+        Ok(MetaENCLog {
+            token: MetadataToken(row[0]),
+            func_code: row[1],
+        })
+    }
+}
+
+/// token of every row of a delta, in table order
+#[derive(Debug, Default)]
+pub struct MetaENCMap {
+    pub token: MetadataToken,
+}
+
+impl MetaItem<MetaENCMap> for MetaENCMap {
+    const TABLE: CLITableId = CLITableId::ENCMap;
+
+    fn from_row(row: &Row) -> Result<MetaENCMap> {
+        Ok(MetaENCMap { token: MetadataToken(row[0]) })
     }
 }
 
@@ -761,37 +1470,237 @@ pub struct MetaAssembly {
     pub flags: u32,
     //AssemblyFlags
     pub public_key: BlobIndex,
-    pub name: Rc<String>,
-    pub culture: Rc<String>,
+    pub name: StrIndex,
+    pub culture: StrIndex,
 }
 
 impl MetaItem<MetaAssembly> for MetaAssembly {
-    fn parse_table(reader: &mut BinaryReader, tilde_stream: &CLITildeStream, string_stream: &CLIStringStream) -> CLITable<MetaAssembly> {
-        let row = tilde_stream.get_table_row(CLITableId::Assembly);
-        let heap_size = tilde_stream.heap_size;
-        let mut data = Vec::new();
-        for _ in 0..row {
-            let hash_alg_id = reader.le_u32();
-            let major_ver = reader.le_u16();
-            let minor_ver = reader.le_u16();
-            let build_num = reader.le_u16();
-            let revision_num = reader.le_u16();
-            let flags = reader.le_u32();
-            let public_key = reader.le_uint(heap_size.blob);
-            let name = reader.le_uint(heap_size.string);
-            let culture = reader.le_uint(heap_size.string);
-            data.push(MetaAssembly {
-                hash_alg_id,
-                major_ver,
-                minor_ver,
-                build_num,
-                revision_num,
-                flags,
-                public_key,
-                name: string_stream.get_str_by_index(name),
-                culture: string_stream.get_str_by_index(culture),
-            });
-        }
-        CLITable::<MetaAssembly> { row, data }
+    const TABLE: CLITableId = CLITableId::Assembly;
+
+    fn from_row(row: &Row) -> Result<MetaAssembly> {
+        Ok(MetaAssembly {
+            hash_alg_id: row[0],
+            major_ver: row[1] as u16,
+            minor_ver: row[2] as u16,
+            build_num: row[3] as u16,
+            revision_num: row[4] as u16,
+            flags: row[5],
+            public_key: row[6],
+            name: row[7],
+            culture: row[8],
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MetaDocument {
+    /// separator and path parts, see PdbFile::document_name
+    pub name: BlobIndex,
+    pub hash_algorithm: GuidIndex,
+    pub hash: BlobIndex,
+    pub language: GuidIndex,
+}
+
+impl MetaItem<MetaDocument> for MetaDocument {
+    const TABLE: CLITableId = CLITableId::Document;
+
+    fn from_row(row: &Row) -> Result<MetaDocument> {
+        Ok(MetaDocument {
+            name: row[0],
+            hash_algorithm: row[1],
+            hash: row[2],
+            language: row[3],
+        })
     }
 }
+
+/// one row per MethodDef, sharing its row id
+#[derive(Debug, Default)]
+pub struct MetaMethodDebugInformation {
+    /// 0 when the sequence points name their initial document themselves
+    pub document: RowIndex,
+    pub sequence_points: BlobIndex,
+}
+
+impl MetaItem<MetaMethodDebugInformation> for MetaMethodDebugInformation {
+    const TABLE: CLITableId = CLITableId::MethodDebugInformation;
+
+    fn from_row(row: &Row) -> Result<MetaMethodDebugInformation> {
+        Ok(MetaMethodDebugInformation {
+            document: row[0],
+            sequence_points: row[1],
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MetaLocalScope {
+    pub method: RowIndex,
+    pub import_scope: RowIndex,
+    pub variable_list: RowIndex,
+    pub constant_list: RowIndex,
+    pub start_offset: u32,
+    pub length: u32,
+}
+
+impl MetaItem<MetaLocalScope> for MetaLocalScope {
+    const TABLE: CLITableId = CLITableId::LocalScope;
+
+    fn from_row(row: &Row) -> Result<MetaLocalScope> {
+        Ok(MetaLocalScope {
+            method: row[0],
+            import_scope: row[1],
+            variable_list: row[2],
+            constant_list: row[3],
+            start_offset: row[4],
+            length: row[5],
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MetaLocalVariable {
+    pub attributes: u16,
+    /// slot in the method's LocalVarSig
+    pub index: u16,
+    pub name: StrIndex,
+}
+
+impl MetaItem<MetaLocalVariable> for MetaLocalVariable {
+    const TABLE: CLITableId = CLITableId::LocalVariable;
+
+    fn from_row(row: &Row) -> Result<MetaLocalVariable> {
+        Ok(MetaLocalVariable {
+            attributes: row[0] as u16,
+            index: row[1] as u16,
+            name: row[2],
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MetaLocalConstant {
+    pub name: StrIndex,
+    pub signature: BlobIndex,
+}
+
+impl MetaItem<MetaLocalConstant> for MetaLocalConstant {
+    const TABLE: CLITableId = CLITableId::LocalConstant;
+
+    fn from_row(row: &Row) -> Result<MetaLocalConstant> {
+        Ok(MetaLocalConstant {
+            name: row[0],
+            signature: row[1],
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MetaImportScope {
+    pub parent: RowIndex,
+    pub imports: BlobIndex,
+}
+
+impl MetaItem<MetaImportScope> for MetaImportScope {
+    const TABLE: CLITableId = CLITableId::ImportScope;
+
+    fn from_row(row: &Row) -> Result<MetaImportScope> {
+        Ok(MetaImportScope {
+            parent: row[0],
+            imports: row[1],
+        })
+    }
+}
+
+/// compiler generated MoveNext of an async or iterator method and the method the user wrote
+#[derive(Debug, Default)]
+pub struct MetaStateMachineMethod {
+    pub move_next_method: RowIndex,
+    pub kickoff_method: RowIndex,
+}
+
+impl MetaItem<MetaStateMachineMethod> for MetaStateMachineMethod {
+    const TABLE: CLITableId = CLITableId::StateMachineMethod;
+
+    fn from_row(row: &Row) -> Result<MetaStateMachineMethod> {
+        Ok(MetaStateMachineMethod {
+            move_next_method: row[0],
+            kickoff_method: row[1],
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MetaCustomDebugInformation {
+    pub parent: TagIndex,
+    pub kind: GuidIndex,
+    pub value: BlobIndex,
+}
+
+impl MetaCustomDebugInformation {
+    pub fn owner(&self) -> Option<HasCustomDebugInformation> {
+        HasCustomDebugInformation::decode(self.parent)
+    }
+}
+
+impl MetaItem<MetaCustomDebugInformation> for MetaCustomDebugInformation {
+    const TABLE: CLITableId = CLITableId::CustomDebugInformation;
+
+    fn from_row(row: &Row) -> Result<MetaCustomDebugInformation> {
+        Ok(MetaCustomDebugInformation {
+            parent: row[0],
+            kind: row[1],
+            value: row[2],
+        })
+    }
+}
+
+/// row a MetadataToken resolved to
+#[derive(Debug)]
+pub enum TableRow<'a> {
+    Module(&'a MetaModule),
+    TypeRef(&'a MetaTypeRef),
+    TypeDef(&'a MetaTypeDef),
+    Field(&'a MetaField),
+    MethodDef(&'a MetaMethodDef),
+    Param(&'a MetaParam),
+    InterfaceImpl(&'a MetaInterfaceImpl),
+    MemberRef(&'a MetaMemberRef),
+    Constant(&'a MetaConstant),
+    CustomAttribute(&'a MetaCustomAttribute),
+    FieldMarshal(&'a MetaFieldMarshal),
+    DeclSecurity(&'a MetaDeclSecurity),
+    ClassLayout(&'a MetaClassLayout),
+    FieldLayout(&'a MetaFieldLayout),
+    StandAloneSig(&'a MetaStandAloneSig),
+    EventMap(&'a MetaEventMap),
+    Event(&'a MetaEvent),
+    PropertyMap(&'a MetaPropertyMap),
+    Property(&'a MetaProperty),
+    MethodSemantics(&'a MetaMethodSemantics),
+    MethodImpl(&'a MetaMethodImpl),
+    ModuleRef(&'a MetaModuleRef),
+    TypeSpec(&'a MetaTypeSpec),
+    ImplMap(&'a MetaImplMap),
+    FieldRVA(&'a MetaFieldRVA),
+    Assembly(&'a MetaAssembly),
+    AssemblyProcessor(&'a MetaAssemblyProcessor),
+    AssemblyOS(&'a MetaAssemblyOS),
+    AssemblyRef(&'a MetaAssemblyRef),
+    AssemblyRefProcessor(&'a MetaAssemblyRefProcessor),
+    AssemblyRefOS(&'a MetaAssemblyRefOS),
+    File(&'a MetaFile),
+    ExportedType(&'a MetaExportedType),
+    ManifestResource(&'a MetaManifestResource),
+    NestedClass(&'a MetaNestedClass),
+    GenericParam(&'a MetaGenericParam),
+    MethodSpec(&'a MetaMethodSpec),
+    GenericParamConstraint(&'a MetaGenericParamConstraint),
+    FieldPtr(&'a MetaFieldPtr),
+    MethodPtr(&'a MetaMethodPtr),
+    ParamPtr(&'a MetaParamPtr),
+    EventPtr(&'a MetaEventPtr),
+    PropertyPtr(&'a MetaPropertyPtr),
+    ENCLog(&'a MetaENCLog),
+    ENCMap(&'a MetaENCMap),
+}
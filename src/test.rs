@@ -3,24 +3,1088 @@ mod tests {
 
     use std::cell::RefCell;
     use std::rc::Rc;
-    use crate::loader::load_dll;
+    use crate::error::Error;
+    use crate::loader::*;
     use crate::context::*;
     use crate::il::*;
+    use crate::reader::*;
+    use crate::debugdir::*;
+    use crate::rsrc::*;
+    use crate::authenticode::*;
+    use crate::strongname::*;
+    use crate::meta::*;
+    use crate::pdb::*;
+    use crate::readytorun::*;
+    use crate::tbl::*;
+    use crate::winpe::*;
 
     #[test]
     fn test_run() {
         let dll_path = "./assets/TestDll.dll";
-        let dll = load_dll(dll_path);
+        let dll = load_dll(dll_path).unwrap();
         let rc_dll = Rc::new(RefCell::new(dll));
 
         let mut context = Context::new();
         context.reflection.load_dll(&rc_dll);
 
-        let test_class = context.reflection.get_class_info(&"Main").unwrap();
+        let test_class = context.reflection.get_class_info(&"Main").unwrap().unwrap();
         let method_add = context.reflection.get_method_info(&"add",&test_class).unwrap();
         let ret = context.exec(&method_add,Some(vec![Data{i32:1574},Data{i32:-433}]));
 
         assert_eq!(ret.unwrap().to_i32(),1574 - 433);
 
+        //ldc.i4 5; stloc.0; ldloc.0; ret without slots sized from a signature
+        let body = [0x20, 0x05, 0x00, 0x00, 0x00, 0x0A, 0x06, 0x2A];
+        let (instructions, _) = parse_il_instructions(&mut BinaryReader::new(&body), body.len() as u32).unwrap();
+        assert_eq!(ExecStack::default().exec(&instructions, None).unwrap().to_i32(), 5);
+        assert_eq!(ExecStack::with_locals(0).exec(&instructions, None).unwrap().to_i32(), 5);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_pe32_plus() {
+        let mut image = vec![0u8; 0x40];
+        image[0] = 0x4D;
+        image[1] = 0x5A;
+        image[0x3C] = 0x40;
+        image.extend_from_slice(b"PE\0\0");
+        //COFF header, AMD64 with one section and a 240 byte optional header
+        for v in [0x8664u16, 1] { image.extend_from_slice(&v.to_le_bytes()); }
+        image.extend_from_slice(&[0; 12]);
+        for v in [0xF0u16, 0x22] { image.extend_from_slice(&v.to_le_bytes()); }
+
+        let opt_header_offset = image.len();
+        image.extend_from_slice(&0x20Bu16.to_le_bytes());
+        image.extend_from_slice(&[0; 22]);
+        image.extend_from_slice(&0x1_8000_0000u64.to_le_bytes());
+        for v in [0x2000u32, 0x200] { image.extend_from_slice(&v.to_le_bytes()); }
+        image.extend_from_slice(&[0; 16]);
+        for v in [0x4000u32, 0x400, 0] { image.extend_from_slice(&v.to_le_bytes()); }
+        image.extend_from_slice(&[3, 0, 0x60, 0x81]);
+        for v in [0x10_0000_0000u64, 0x1000, 0x20_0000_0000, 0x2000] { image.extend_from_slice(&v.to_le_bytes()); }
+        for v in [0u32, 16] { image.extend_from_slice(&v.to_le_bytes()); }
+        let data_directory_offset = image.len();
+        for index in 0..16u32 {
+            let dir = if index == 14 { [0x2008u32, 0x48] } else { [0, 0] };
+            for v in dir { image.extend_from_slice(&v.to_le_bytes()); }
+        }
+
+        image.extend_from_slice(b".text\0\0\0");
+        for v in [0x1000u32, 0x2000, 0x200, 0x400, 0, 0] { image.extend_from_slice(&v.to_le_bytes()); }
+        image.extend_from_slice(&[0; 4]);
+        image.extend_from_slice(&0x6000_0020u32.to_le_bytes());
+
+        let pe = WinPe::parse_winpe(&mut BinaryReader::new(&image)).unwrap();
+        assert_eq!(pe.magic, PeMagic::PE32Plus);
+        assert_eq!(pe.machine_type(), Machine::AMD64);
+        assert_eq!(pe.image_base, 0x1_8000_0000);
+        assert_eq!(pe.base_of_data, 0);
+        assert_eq!(pe.size_of_stack_reserve, 0x10_0000_0000);
+        assert_eq!(pe.size_of_stack_commit, 0x1000);
+        assert_eq!(pe.size_of_heap_reserve, 0x20_0000_0000);
+        assert_eq!(pe.size_of_heap_commit, 0x2000);
+        assert_eq!(pe.num_rva_and_sizes, 16);
+        assert_eq!(pe.opt_header_offset, opt_header_offset);
+        assert_eq!(pe.data_directory_offset, opt_header_offset + 112);
+        assert_eq!(pe.data_directory_offset, data_directory_offset);
+        assert_eq!((pe.clr_runtime_header.rva, pe.clr_runtime_header.size), (0x2008, 0x48));
+        assert_eq!(pe.sections[0].name, ".text");
+        assert_eq!(pe.rva_to_offset(0x2010).unwrap(), 0x410);
+    }
+
+    #[test]
+    fn test_runtime_method() {
+        let mut dll = load_dll("./assets/TestDll.dll").unwrap();
+        //a runtime implemented method, like a delegate's Invoke, does not stop the class loading
+        let ctor = dll.clidata.tbl_methoddef.data.iter().position(|m| dll.string(m.name).unwrap() == ".ctor").unwrap();
+        dll.clidata.tbl_methoddef.data[ctor].impl_flags |= 0x3;
+        let rc_dll = Rc::new(RefCell::new(dll));
+        let mut context = Context::new();
+        context.reflection.load_dll(&rc_dll);
+
+        let test_class = context.reflection.get_class_info(&"Main").unwrap().unwrap();
+        let ctor = context.reflection.get_method_info(&".ctor", &test_class).unwrap();
+        assert!(!ctor.instruction.borrow().has_body);
+        let method_add = context.reflection.get_method_info(&"add", &test_class).unwrap();
+        assert!(method_add.instruction.borrow().has_body);
+    }
+
+    #[test]
+    fn test_section_rva() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let pe = &dll.pe;
+        assert_eq!(pe.sections.len(), pe.num_section as usize);
+        assert_eq!(pe.sections[0].name, ".text");
+        assert_eq!(pe.magic, PeMagic::PE32);
+        assert_eq!(pe.machine_type(), Machine::I386);
+        assert_eq!(pe.image_base, 0x1000_0000);
+        assert_eq!(pe.num_rva_and_sizes, 16);
+
+        let text = &pe.sections[0];
+        assert_eq!(pe.rva_to_offset(text.virtual_addr).unwrap(), text.pointer_to_raw_data as usize);
+        assert!(pe.rva_to_offset(0x10).is_err());
+        assert!(pe.rva_to_offset(0xFFFF_0000).is_err());
+    }
+
+    #[test]
+    fn test_load_error() {
+        match load_dll("./assets/NotExist.dll") {
+            Err(Error::Io(_)) => (),
+            r => panic!("expect io error, got {:?}", r.map(|_| ())),
+        }
+        match DllFile::new(vec![0; 256]) {
+            Err(Error::BadSignature { offset: 0, .. }) => (),
+            r => panic!("expect bad signature, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_reader_bounds() {
+        let data = [1u8, 2, 3, 4, 5, 6];
+        let mut reader = BinaryReader::new(&data);
+        reader.seek(4);
+        assert_eq!(reader.le_u32(), Err(ReadError { offset: 4, len: 4 }));
+        assert_eq!(reader.le_u16(), Ok(0x0605));
+
+        let mut sub = reader.sub_reader(1, 2).unwrap();
+        assert_eq!(sub.le_u16(), Ok(0x0302));
+        assert_eq!(sub.le_u8(), Err(ReadError { offset: 3, len: 1 }));
+        assert!(reader.sub_reader(4, 3).is_err());
+
+        let dll = std::fs::read("./assets/TestDll.dll").unwrap();
+        match DllFile::new(dll[..0x280].to_vec()) {
+            Err(Error::Truncated { .. }) => (),
+            r => panic!("expect truncated, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_load_sources() {
+        let data = std::fs::read("./assets/TestDll.dll").unwrap();
+
+        let borrowed = DllFile::from_bytes(&data).unwrap();
+        let mapped = DllFile::from_mapped(data.clone()).unwrap();
+        let read = DllFile::from_reader(std::io::Cursor::new(data.clone())).unwrap();
+        assert_eq!(borrowed.bytes().as_ptr(), data.as_ptr());
+        for dll in [&borrowed, &mapped, &read].iter() {
+            assert_eq!(dll.bytes().len(), data.len());
+            assert_eq!(dll.clidata.tbl_methoddef.row, 2);
+        }
+    }
+
+    #[test]
+    fn test_debug_directory() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let entries = dll.debug_directory().unwrap();
+        assert_eq!(entries.len(), 3);
+
+        assert!(entries[0].is_portable_pdb());
+        match &entries[0].info {
+            DebugInfo::CodeView(cv) => {
+                assert_eq!(cv.guid.to_string(), "7ae17937-d6f1-4ec5-afc3-ea081473363c");
+                assert_eq!(cv.age, 1);
+                assert!(cv.path.ends_with("TestDll.pdb"));
+                assert_eq!(cv.symbol_key(), "7AE17937D6F14EC5AFC3EA081473363C1");
+            }
+            info => panic!("expect codeview, got {:?}", info),
+        }
+        match &entries[1].info {
+            DebugInfo::PdbChecksum { algorithm, checksum } => {
+                assert_eq!(*algorithm, "SHA256");
+                assert_eq!(checksum.len(), 32);
+            }
+            info => panic!("expect pdb checksum, got {:?}", info),
+        }
+        assert_eq!(entries[2].get_type(), DebugType::Reproducible);
+
+        //an NB10 record does not stop the other entries from being read
+        let mut data = std::fs::read("./assets/TestDll.dll").unwrap();
+        let pos = entries[0].pointer_to_raw_data as usize;
+        data[pos..pos + 4].copy_from_slice(b"NB10");
+        let dll = DllFile::from_bytes(&data).unwrap();
+        let entries = dll.debug_directory().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].get_type(), DebugType::CodeView);
+        assert!(matches!(entries[0].info, DebugInfo::Other));
+        assert!(matches!(entries[1].info, DebugInfo::PdbChecksum { .. }));
+    }
+
+    #[test]
+    fn test_version_info() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let res = dll.resources().unwrap();
+        assert_eq!(res.len(), 1);
+        assert!(res[0].is_type(RT_VERSION));
+        assert_eq!(res[0].name, ResourceName::Id(1));
+
+        let info = dll.version_info().unwrap().unwrap();
+        assert_eq!(info.fixed.as_ref().unwrap().file_version(), [1, 0, 0, 0]);
+        assert_eq!(info.file_version(), Some("1.0.0.0"));
+        assert_eq!(info.product_version(), Some("1.0.0"));
+        assert_eq!(info.company_name(), Some("TestDll"));
+        assert_eq!(info.get("Assembly Version"), Some("1.0.0.0"));
+        assert_eq!(info.translations, vec![(0, 0x04b0)]);
+
+        //blocks are nested from the inside out, each one wraps the previous
+        fn nest(keys: &[&str]) -> Vec<u8> {
+            let mut inner = Vec::new();
+            for key in keys.iter().rev() {
+                let mut block = vec![0; 6];
+                block[4] = 1;
+                block.extend(key.encode_utf16().chain(Some(0)).flat_map(|c| c.to_le_bytes().to_vec()));
+                while block.len() % 4 != 0 {
+                    block.push(0);
+                }
+                block.extend_from_slice(&inner);
+                let len = block.len() as u16;
+                block[0..2].copy_from_slice(&len.to_le_bytes());
+                inner = block;
+            }
+            inner
+        }
+        assert!(VersionInfo::parse(&nest(&["VS_VERSION_INFO", "StringFileInfo", "000004b0", "A"])).is_ok());
+        match VersionInfo::parse(&nest(&["VS_VERSION_INFO", "StringFileInfo", "000004b0", "A", "B"])) {
+            Err(Error::InvalidValue { what: "version block nesting", value: 4 }) => (),
+            r => panic!("{:?}", r),
+        }
+        let mut deep = vec!["A"; 5000];
+        assert!(matches!(VersionInfo::parse(&nest(&deep)), Err(Error::BadSignature { .. })));
+        deep[0] = "VS_VERSION_INFO";
+        assert!(matches!(VersionInfo::parse(&nest(&deep)), Err(Error::InvalidValue { .. })));
+
+        //a subdirectory that points back at the root
+        let mut data = std::fs::read("./assets/TestDll.dll").unwrap();
+        let root = dll.pe.rva_to_offset(dll.pe.resource_tbl.rva).unwrap();
+        data[root + 20..root + 24].copy_from_slice(&0x8000_0000u32.to_le_bytes());
+        let dll = DllFile::from_bytes(&data).unwrap();
+        match dll.resources() {
+            Err(Error::InvalidValue { what: "resource directory visited twice", value: 0 }) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_authenticode() {
+        let mut data = std::fs::read("./assets/TestDll.dll").unwrap();
+        let dll = DllFile::from_bytes(&data).unwrap();
+        assert!(dll.certificates().unwrap().is_empty());
+        let digest = dll.authenticode_digest(DigestAlgorithm::Sha256).unwrap();
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "4fd3c86e2d19af78d4f59c3d28de4a3f5e5bbf940910ff187b6183d00e6962c1");
+        drop(dll);
+
+        //checksum is excluded from the digest
+        data[0x98 + 64] ^= 0xFF;
+        let dll = DllFile::from_bytes(&data).unwrap();
+        assert_eq!(dll.authenticode_digest(DigestAlgorithm::Sha256).unwrap(), digest);
+        drop(dll);
+
+        //so are the certificate table and its directory entry
+        let table = data.len() as u32;
+        data.extend_from_slice(&[12, 0, 0, 0, 0x00, 0x02, 0x02, 0x00, 1, 2, 3, 4, 0, 0, 0, 0]);
+        data[0x118..0x11C].copy_from_slice(&table.to_le_bytes());
+        data[0x11C..0x120].copy_from_slice(&16u32.to_le_bytes());
+        let dll = DllFile::from_bytes(&data).unwrap();
+        let certs = dll.certificates().unwrap();
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0].revision, WIN_CERT_REVISION_2_0);
+        assert!(certs[0].is_pkcs7());
+        assert_eq!(certs[0].data, &[1, 2, 3, 4]);
+        assert_eq!(dll.authenticode_digest(DigestAlgorithm::Sha256).unwrap(), digest);
+
+        //no certificate directory entry at all
+        let mut pe = load_dll("./assets/TestDll.dll").unwrap().pe;
+        pe.num_rva_and_sizes = 4;
+        let checksum = pe.opt_header_offset + 64;
+        assert_eq!(authenticode_ranges(&pe, data.len()).unwrap(), vec![0..checksum, checksum + 4..data.len()]);
+    }
+
+    #[test]
+    fn test_strong_name() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        assert!(dll.strong_name_signature().unwrap().is_none());
+        assert_eq!(dll.strong_name_status().unwrap(), StrongNameStatus::Unsigned);
+
+        let hex = |s: &str| -> Vec<u8> {
+            (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+        };
+        //512 bit test key, signature over sha1("strong name")
+        let blob = hex("00240000048000005400000006020000002400005253413100020000010001003796d397a39ce24a2f5c424b\
+                        60a2f8ac7e56a814edd5397526ed0656bdfa6e49c1827c2b8acdca1cebc7ddcd7473b931218909b333c09d0f8b4c195bf998d69e");
+        let mut signature = hex("f760e00ee7a54d79eb539f4bb0c5c463e717eaecec8039ff083e26a2e4ef0941\
+                                 9060affa2738c1d1b9ce9e674fcbefda8eb7da4a9bd0ab311adb15091b8f611c");
+        let key = StrongNameKey::parse(&blob).unwrap();
+        assert_eq!(key.hash_alg_id, CALG_SHA1);
+        assert_eq!(key.bit_len, 512);
+        assert_eq!(key.exponent, 65537);
+
+        use sha1::{Digest, Sha1};
+        let hash = Sha1::digest(b"strong name").to_vec();
+        assert!(key.verify(&hash, &signature).unwrap());
+        signature[0] ^= 1;
+        assert!(!key.verify(&hash, &signature).unwrap());
+
+        let ecma = [0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0];
+        assert!(StrongNameKey::is_ecma_key(&ecma));
+        assert!(matches!(StrongNameKey::parse(&ecma), Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn test_strong_name_image() {
+        use num_bigint::BigUint;
+        let hex = |s: &str| -> Vec<u8> {
+            (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+        };
+        //512 bit test key pair, big endian
+        let n = BigUint::from_bytes_be(&hex("f35927e8ea01303f66611a95dec1c30284e947f734163a03babcd0afffb300af\
+                                             52000785334382ce3c7b0216dd0d5c2b8438aefa4f9f55280cc7283f1a845055"));
+        let d = BigUint::from_bytes_be(&hex("7e1ee10e57e951c62a1b7d99db1f7520631e3c8c2454b8b0a221a5cef1a9265a\
+                                             b45eb8764c6076140ce332faf30915f662a2ba36f4315b60b6ae164e428364c1"));
+        let mut key = Vec::new();
+        for v in [CALG_RSA_SIGN, CALG_SHA1, 0x54, 0x0000_0206, CALG_RSA_SIGN] { key.extend_from_slice(&v.to_le_bytes()); }
+        key.extend_from_slice(b"RSA1");
+        for v in [512u32, 65537] { key.extend_from_slice(&v.to_le_bytes()); }
+        key.extend_from_slice(&n.to_bytes_le());
+
+        //give the image a public key and reserve 64 bytes at the end of .text for the signature
+        let mut data = std::fs::read("./assets/TestDll.dll").unwrap();
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let mut pe = dll.pe;
+        let mut cli = dll.clidata;
+        let heap = data.len();
+        data.extend_from_slice(&[0, key.len() as u8]);
+        data.extend_from_slice(&key);
+        cli.blob_heap = BlobHeap { base: heap, size: key.len() + 2 };
+        cli.tbl_assembly.data[0].public_key = 1;
+        let text = &pe.sections[0];
+        let rva = text.virtual_addr + text.mapped_size().min(text.size_of_raw_data) - 64;
+        cli.header.strong_name_signature = DataPointer { rva, size: 64 };
+        let sig = pe.rva_to_offset(rva).unwrap();
+        data[sig..sig + 64].iter_mut().for_each(|b| *b = 0);
+        assert_eq!(verify_strong_name(&pe, &cli, &data).unwrap(), StrongNameStatus::DelaySigned);
+
+        cli.header.flags = ComImageFlags::STRONGNAMESIGNED;
+        assert_eq!(verify_strong_name(&pe, &cli, &data).unwrap(), StrongNameStatus::Invalid);
+
+        //sign the hash, which skips the signature blob being written
+        let hash = strong_name_hash(&pe, &cli, &data, CALG_SHA1).unwrap();
+        let digest: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(digest, "9e1205e6fe33a70fce700a220f17e89a3052b4b4");
+        let prefix = [0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14];
+        let mut em = vec![0xFF; 64];
+        em[0] = 0;
+        em[1] = 1;
+        em[64 - hash.len() - prefix.len() - 1] = 0;
+        em[64 - hash.len() - prefix.len()..64 - hash.len()].copy_from_slice(&prefix);
+        em[64 - hash.len()..].copy_from_slice(&hash);
+        let mut signature = BigUint::from_bytes_be(&em).modpow(&d, &n).to_bytes_le();
+        signature.resize(64, 0);
+        data[sig..sig + 64].copy_from_slice(&signature);
+        assert_eq!(strong_name_signature(&pe, &cli, &data).unwrap().unwrap(), &signature[..]);
+        assert_eq!(verify_strong_name(&pe, &cli, &data).unwrap(), StrongNameStatus::Valid);
+
+        //checksum, certificate table and its directory entry are excluded
+        data[pe.opt_header_offset + 64] ^= 0xFF;
+        let table = data.len() as u32;
+        data.extend_from_slice(&[16, 0, 0, 0, 0x00, 0x02, 0x02, 0x00, 1, 2, 3, 4, 5, 6, 7, 8]);
+        pe.certificate_tbl = DataPointer { rva: table, size: 16 };
+        let cert_entry = pe.data_directory_offset + 4 * 8;
+        data[cert_entry..cert_entry + 4].copy_from_slice(&table.to_le_bytes());
+        data[cert_entry + 4..cert_entry + 8].copy_from_slice(&16u32.to_le_bytes());
+        assert_eq!(verify_strong_name(&pe, &cli, &data).unwrap(), StrongNameStatus::Valid);
+
+        //so is the padding between the section table and the first section
+        let headers_end = pe.opt_header_offset + pe.sz_opt_header as usize + pe.sections.len() * 40;
+        assert!(headers_end < pe.sections[0].pointer_to_raw_data as usize);
+        data[headers_end] ^= 0xFF;
+        assert_eq!(verify_strong_name(&pe, &cli, &data).unwrap(), StrongNameStatus::Valid);
+
+        //any other byte is covered
+        data[0x50] ^= 0xFF;
+        assert_eq!(verify_strong_name(&pe, &cli, &data).unwrap(), StrongNameStatus::Invalid);
+    }
+
+    #[test]
+    fn test_native_array() {
+        //two byte unsigned
+        assert_eq!(decode_unsigned(&mut BinaryReader::new(&[0xB1, 0x04])).unwrap(), 300);
+
+        //entries 0 and 1: block offsets, three left turns, then a node with both children
+        let section = [0x10, 0x01, 0x02, 0x02, 0x02, 0x16, 0x0C, 0x2A];
+        let reader = BinaryReader::new(&section);
+        assert_eq!(native_array_lookup(&reader, 0).unwrap(), Some(6));
+        assert_eq!(native_array_lookup(&reader, 1).unwrap(), Some(7));
+        assert_eq!(native_array_lookup(&reader, 2).unwrap(), None);
+
+        //the same array as the MethodDefEntryPoints of TestDll.dll
+        let mut data = std::fs::read("./assets/TestDll.dll").unwrap();
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let pe = &dll.pe;
+        let text = &pe.sections[0];
+        let rva = text.virtual_addr + text.mapped_size().min(text.size_of_raw_data) - 0x80;
+        let pos = pe.rva_to_offset(rva).unwrap();
+        data[pos..pos + section.len()].copy_from_slice(&section);
+        for i in 0..6u32 {
+            let entry = pos + 0x20 + i as usize * 8;
+            data[entry..entry + 4].copy_from_slice(&(0x1000 + i * 0x10).to_le_bytes());
+        }
+        for (i, v) in [0x2008u32, 0x48, 0x3000, 0x40].iter().enumerate() {
+            data[pos + 0x60 + i * 4..pos + 0x64 + i * 4].copy_from_slice(&v.to_le_bytes());
+        }
+        let header = ReadyToRunHeader {
+            major_version: 9,
+            minor_version: 0,
+            flags: READYTORUN_FLAG_COMPONENT,
+            sections: vec![
+                ReadyToRunSection { section_type: ReadyToRunSectionType::MethodDefEntryPoints as u32, section: DataPointer { rva, size: 8 } },
+                ReadyToRunSection { section_type: ReadyToRunSectionType::RuntimeFunctions as u32, section: DataPointer { rva: rva + 0x20, size: 48 } },
+                ReadyToRunSection { section_type: ReadyToRunSectionType::ComponentAssemblies as u32, section: DataPointer { rva: rva + 0x60, size: 16 } },
+            ],
+        };
+        assert_eq!(header.method_entry_point(pe, &data, 1).unwrap(), Some(MethodEntryPoint {
+            runtime_function_index: 3,
+            begin_rva: 0x1030,
+            has_fixups: false,
+        }));
+        assert_eq!(header.method_entry_point(pe, &data, 2).unwrap(), Some(MethodEntryPoint {
+            runtime_function_index: 5,
+            begin_rva: 0x1050,
+            has_fixups: true,
+        }));
+        assert_eq!(header.method_entry_point(pe, &data, 3).unwrap(), None);
+        assert_eq!(header.method_entry_point(pe, &data, 0).unwrap(), None);
+
+        let components = header.component_assemblies(pe, &data).unwrap();
+        assert_eq!(components.len(), 1);
+        assert_eq!((components[0].cor_header.rva, components[0].cor_header.size), (0x2008, 0x48));
+        assert_eq!(components[0].ready_to_run_core_header.rva, 0x3000);
+
+        //a linux x64 image keeps the 12 byte RUNTIME_FUNCTION
+        let mut dll = dll;
+        dll.pe.machine = Machine::AMD64 as u16 ^ 0x7B79;
+        assert_eq!(dll.pe.machine_type(), Machine::Unknown);
+        assert_eq!(dll.pe.native_machine(), Machine::AMD64);
+        for i in 0..4u32 {
+            let entry = pos + 0x20 + i as usize * 12;
+            data[entry..entry + 4].copy_from_slice(&(0x1000 + i * 0x10).to_le_bytes());
+        }
+        assert_eq!(header.method_entry_point(&dll.pe, &data, 1).unwrap().unwrap().begin_rva, 0x1030);
+    }
+
+    #[test]
+    fn test_cli_flags() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let header = &dll.clidata.header;
+        assert_eq!(header.flags, ComImageFlags::ILONLY);
+        assert!(header.is_il_only());
+        assert!(!header.is_mixed_mode());
+        assert!(!header.requires_32bit());
+        assert!(!header.is_strong_name_signed());
+        assert_eq!(header.entry_point(), EntryPoint::None);
+        assert!(!dll.is_ready_to_run().unwrap());
+
+        let mut rtr = b"RTR\0".to_vec();
+        rtr.extend_from_slice(&[9, 0, 2, 0, 0x20, 0, 0, 0, 1, 0, 0, 0]);
+        rtr.extend_from_slice(&[115, 0, 0, 0, 0x00, 0x20, 0, 0, 32, 0, 0, 0]);
+        let header = ReadyToRunHeader::parse(&mut BinaryReader::new(&rtr)).unwrap();
+        assert_eq!((header.major_version, header.minor_version), (9, 2));
+        assert!(header.is_component());
+        assert_eq!(header.sections[0].get_type(), ReadyToRunSectionType::ComponentAssemblies);
+        assert_eq!(header.get_section(ReadyToRunSectionType::ComponentAssemblies).unwrap().rva, 0x2000);
+        assert!(header.get_section(ReadyToRunSectionType::RuntimeFunctions).is_none());
+    }
+
+    #[test]
+    fn test_tables() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        let tilde = &cli.tilde_stream;
+
+        //Param sits between MethodDef and MemberRef
+        assert_eq!(cli.tbl_param.row, 2);
+        let sequence: Vec<u16> = cli.tbl_param.data.iter().map(|p| p.sequence).collect();
+        assert_eq!(sequence, vec![1, 2]);
+        assert_eq!(tilde.get_row_size(CLITableId::MethodDef), 14);
+        assert_eq!(tilde.get_table_pos(CLITableId::MemberRef),
+                   tilde.get_table_pos(CLITableId::Param) + 2 * tilde.get_row_size(CLITableId::Param));
+        assert_eq!(cli.tbl_member_ref.row, 11);
+        assert!(cli.tbl_member_ref.data.iter().any(|m| dll.string(m.name).unwrap() == ".ctor"));
+
+        //the last table ends inside the #~ stream
+        let (off, size) = cli.meta.get_stream_rva("#~");
+        let last = CLITableId::AssemblyRef;
+        let end = tilde.get_table_pos(last) + tilde.get_row_size(last) * tilde.get_table_row(last) as usize;
+        assert!(end <= cli.meta.meta_pos + off + size);
+        assert!(end + 4 >= cli.meta.meta_pos + off + size);
+    }
+
+    #[test]
+    fn test_coded_index() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+
+        let main = cli.tbl_typedef.get_data_by_filter(&|t| dll.string(t.name).unwrap() == "Main").unwrap();
+        let base = main.base_type().unwrap();
+        assert_eq!(base.table(), CLITableId::TypeRef);
+        let object = cli.tbl_typeref.get_data_by_index(base.row() as usize - 1);
+        assert_eq!(dll.string(object.name).unwrap(), "Object");
+        assert!(matches!(object.scope(), Some(ResolutionScope::AssemblyRef(1))));
+        assert_eq!(cli.tbl_typedef.get_data_by_index(0).base_type(), None);
+
+        for attr in &cli.tbl_custom_attribute.data {
+            assert_eq!(attr.constructor().unwrap().table(), CLITableId::MemberRef);
+            assert!(attr.owner().is_some());
+        }
+
+        let value = CLIColumnType::HasCustomAttribute.encode(CLITableId::DeclSecurity, 3).unwrap();
+        assert_eq!(value, 3 << 5 | 8);
+        assert_eq!(HasCustomAttribute::decode(value).unwrap().token(), (CLITableId::DeclSecurity, 3));
+        assert_eq!(CustomAttributeType::decode(1 << 3 | 1), None);
+        assert_eq!(Implementation::decode(2 << 2), Some(Implementation::File(2)));
+    }
+
+    #[test]
+    fn test_column_width() {
+        //tilde stream header with only TypeDef present and extra data after the row counts
+        let tilde = |typedef_rows: u32| {
+            let mut data = vec![0, 0, 0, 0, 2, 0, HEAP_EXTRA_DATA | HEAP_BLOB_4, 1];
+            data.extend_from_slice(&(1u64 << CLITableId::TypeDef as u8).to_le_bytes());
+            data.extend_from_slice(&0u64.to_le_bytes());
+            data.extend_from_slice(&typedef_rows.to_le_bytes());
+            data.extend_from_slice(&0xDEADu32.to_le_bytes());
+            data.resize(data.len() + typedef_rows as usize * 24, 0);
+            CLITildeStream::parse(&mut BinaryReader::new(&data)).unwrap()
+        };
+
+        //TypeDefOrRef keeps 2 tag bits, 14 bits are left for the row
+        let small = tilde(0x3FFF);
+        assert_eq!(small.get_column_width(CLIColumn::Coded(CLIColumnType::TypeDefOrRef)), 2);
+        assert_eq!(small.extra_data, Some(0xDEAD));
+        assert_eq!(small.get_table_pos(CLITableId::TypeDef), 32);
+        assert_eq!(small.get_column_width(CLIColumn::Blob), 4);
+        assert_eq!(small.get_row_size(CLITableId::TypeDef), 4 + 2 + 2 + 2 + 2 + 2);
+
+        let large = tilde(0x4000);
+        assert_eq!(large.get_column_width(CLIColumn::Coded(CLIColumnType::TypeDefOrRef)), 4);
+        assert_eq!(large.get_column_width(CLIColumn::Coded(CLIColumnType::HasCustomAttribute)), 4);
+        assert_eq!(large.get_column_width(CLIColumn::Coded(CLIColumnType::TypeOrMethodDef)), 2);
+        assert_eq!(large.get_column_width(CLIColumn::Index(CLITableId::TypeDef)), 2);
+        assert_eq!(tilde(0x10000).get_column_width(CLIColumn::Index(CLITableId::TypeDef)), 4);
+
+        let layout = large.layout();
+        assert_eq!(layout.len(), 1);
+        assert!(layout[0].to_string().starts_with("02 TypeDef"));
+
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let layout = dll.clidata.tilde_stream.layout();
+        assert_eq!(layout.iter().map(|l| l.table).collect::<Vec<_>>(), dll.clidata.tilde_stream.table_valid);
+        assert_eq!(layout[0].to_string(), "00 Module                   rows        1 size  10 at 0x0000031c | U16:2 Str:2 Guid:2 Guid:2 Guid:2");
+
+        //row counts that run past the end of the stream
+        let tilde = &dll.clidata.tilde_stream;
+        assert_eq!(tilde.extra_data, None);
+        let module_rows = tilde.get_table_pos(CLITableId::Module) - tilde.rows.len() * 4;
+        let mut data = std::fs::read("./assets/TestDll.dll").unwrap();
+        data[module_rows..module_rows + 4].copy_from_slice(&400_000_000u32.to_le_bytes());
+        assert!(matches!(DllFile::from_bytes(&data), Err(Error::Truncated { .. })));
+    }
+
+    #[test]
+    fn test_metadata_token() {
+        let token = MetadataToken::new(CLITableId::MethodDef, 1);
+        assert_eq!(token.to_string(), "0x06000001");
+        assert_eq!(token.table(), Some(CLITableId::MethodDef));
+        assert_eq!(token.rid(), 1);
+        assert!(MetadataToken(0x7000_0001).is_user_string());
+        assert_eq!(TypeDefOrRef::TypeRef(3).metadata_token(), MetadataToken(0x0100_0003));
+
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        assert!(cli.header.entry_point_token.is_nil());
+        assert!(matches!(cli.resolve(token).unwrap(), TableRow::MethodDef(m) if dll.string(m.name).unwrap() == "add"));
+        assert!(cli.resolve(MetadataToken::new(CLITableId::MethodDef, 3)).is_err());
+        assert!(cli.resolve(MetadataToken(0x7000_0001)).is_err());
+
+        let rc_dll = Rc::new(RefCell::new(dll));
+        let mut context = Context::new();
+        context.reflection.load_dll(&rc_dll);
+        let main = context.reflection.get_class_info("Main").unwrap().unwrap();
+
+        let add = context.reflection.get_method_info("add", &main).unwrap();
+        let locals = add.instruction.borrow().local_var_sig_tok;
+        assert_eq!(locals, MetadataToken::new(CLITableId::StandAloneSig, 1));
+        assert!(matches!(rc_dll.borrow().clidata.resolve(locals).unwrap(), TableRow::StandAloneSig(_)));
+
+        //base constructor call goes through a MemberRef
+        let ctor = context.reflection.get_method_info(".ctor", &main).unwrap();
+        let body = ctor.instruction.borrow();
+        let call = body.instruction.iter().find(|i| matches!(i.op, OpCode::call)).unwrap();
+        let target = call.data.to_token();
+        assert_eq!(target.table(), Some(CLITableId::MemberRef));
+        let dll = rc_dll.borrow();
+        match dll.clidata.resolve(target).unwrap() {
+            TableRow::MemberRef(m) => assert_eq!(dll.string(m.name).unwrap(), ".ctor"),
+            row => panic!("{:?}", row),
+        };
+    }
+
+    #[test]
+    fn test_user_strings() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        assert_eq!(dll.user_strings().unwrap().count(), 0);
+        assert_eq!(dll.user_string(0).unwrap(), UserString::Text(String::new()));
+
+        let data = [0x00, 0x05, b'H', 0x00, b'i', 0x00, 0x00, 0x03, 0x00, 0xD8, 0x01, 0x00, 0x00];
+        let heap = UserStringHeap { base: 0, size: data.len() };
+        assert_eq!(heap.get(&data, 1).unwrap().as_str(), Some("Hi"));
+        assert_eq!(heap.get(&data, 7).unwrap(), UserString::Utf16(vec![0xD800]));
+
+        let all: Vec<(u32, UserString)> = heap.iter(&data).unwrap().map(|s| s.unwrap()).collect();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0], (1, UserString::Text("Hi".to_string())));
+        assert_eq!(all[1].0, 7);
+        assert!(heap.get(&data, data.len() as u32).is_err());
+
+        //ldstr "Hi"; ret
+        let body = [0x72, 0x01, 0x00, 0x00, 0x70, 0x2A];
+        let (instructions, _) = parse_il_instructions(&mut BinaryReader::new(&body), body.len() as u32).unwrap();
+        assert_eq!(instructions.len(), 2);
+        let token = unsafe { instructions[0].data.token };
+        assert!(token.is_user_string());
+        assert_eq!(heap.get(&data, token.rid()).unwrap().as_str(), Some("Hi"));
+
+        assert_eq!(dll.ldstr_string(MetadataToken(0x7000_0000)).unwrap(), UserString::Text(String::new()));
+        assert!(dll.ldstr_string(MetadataToken::new(CLITableId::Field, 1)).is_err());
+    }
+
+    #[test]
+    fn test_guid_heap() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        assert_eq!(cli.guid_heap.guids.len(), 1);
+        assert_eq!(dll.mvid().unwrap().to_string(), "546f6d5d-b261-4343-b801-29d5613d49f0");
+
+        let module = cli.tbl_module.get_data_by_index(0);
+        assert_eq!(module.mvid, 1);
+        assert_eq!(cli.guid_heap.get(module.enc_id), None);
+        assert_eq!(cli.guid_heap.get(module.enc_base_id), None);
+        assert_eq!(cli.guid_heap.get(2), None);
+    }
+
+    #[test]
+    fn test_string_heap() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        assert_eq!(dll.string(0).unwrap(), "");
+
+        let data = b"\0TypeName\0\xC3\x28\0tail";
+        let heap = CLIStringStream { base: 0, size: data.len() }.view(data).unwrap();
+        assert_eq!(heap.get(1).unwrap(), "TypeName");
+        //suffix shared with TypeName
+        assert_eq!(heap.get(5).unwrap(), "Name");
+        assert_eq!(heap.get(9).unwrap(), "");
+        match heap.get(10) {
+            Err(Error::InvalidUtf8 { offset: 10 }) => (),
+            r => panic!("{:?}", r),
+        };
+        assert!(heap.get(13).is_err());
+        assert!(heap.get(data.len() as u32).is_err());
+
+        //an obfuscated name only fails when it is asked for
+        let object = dll.clidata.tbl_typeref.data.iter().find(|t| dll.string(t.name).unwrap() == "Object").unwrap();
+        let pos = dll.clidata.string_stream.base + object.name as usize;
+        let mut bytes = std::fs::read("./assets/TestDll.dll").unwrap();
+        bytes[pos] = 0xFF;
+        let patched = DllFile::from_bytes(&bytes).unwrap();
+        assert!(matches!(patched.string(object.name), Err(Error::InvalidUtf8 { .. })));
+        let main = patched.clidata.tbl_typedef.get_data_by_filter(&|t| patched.string(t.name).map_or(false, |n| n == "Main")).unwrap();
+        assert!(patched.clidata.type_name(patched.bytes(), main.base_type().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_blob_heap() {
+        let unsigned: [(&[u8], u32); 5] = [(&[0x03], 3), (&[0x80, 0x80], 0x80), (&[0xAE, 0x57], 0x2E57),
+            (&[0xC0, 0x00, 0x40, 0x00], 0x4000), (&[0xDF, 0xFF, 0xFF, 0xFF], 0x1FFF_FFFF)];
+        for (bytes, value) in unsigned.iter() {
+            assert_eq!(BinaryReader::new(bytes).compressed_u32().unwrap(), *value);
+        }
+        let signed: [(&[u8], i32); 7] = [(&[0x06], 3), (&[0x7B], -3), (&[0x80, 0x80], 64), (&[0x01], -64),
+            (&[0xC0, 0x00, 0x40, 0x00], 8192), (&[0x80, 0x01], -8192), (&[0xC0, 0x00, 0x00, 0x01], -268435456)];
+        for (bytes, value) in signed.iter() {
+            assert_eq!(BinaryReader::new(bytes).compressed_i32().unwrap(), *value);
+        }
+
+        //a two byte length prefix and an empty blob
+        let mut data = vec![0x00, 0x81, 0x00];
+        data.extend((0..0x100).map(|b| b as u8));
+        let heap = BlobHeap { base: 0, size: data.len() };
+        assert_eq!(heap.get(&data, 0).unwrap(), &[] as &[u8]);
+        assert_eq!(heap.get(&data, 1).unwrap().len(), 0x100);
+        assert!(BlobHeap { base: 0, size: 0x80 }.get(&data, 1).is_err());
+
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        let add = cli.tbl_methoddef.get_data_by_filter(&|m: &MetaMethodDef| dll.string(m.name).unwrap() == "add").unwrap();
+        let sig: MethodDefSig = cli.parse_signature(dll.bytes(), add.signature).unwrap();
+        assert_eq!(sig.param_count as usize, sig.params.len());
+    }
+
+    /// minimal portable pdb: one document and debug info for two methods, the first with
+    /// a visible, a hidden and a second visible sequence point
+    fn portable_pdb() -> Vec<u8> {
+        fn pad(mut v: Vec<u8>) -> Vec<u8> {
+            while v.len() % 4 != 0 {
+                v.push(0);
+            }
+            v
+        }
+        let mut pdb = vec![0x11; 20];
+        pdb.extend(&0x0600_0001u32.to_le_bytes());
+        pdb.extend(&(1u64 << 6).to_le_bytes());
+        pdb.extend(&2u32.to_le_bytes());
+
+        let mut tilde = vec![0, 0, 0, 0, 2, 0, 0, 1];
+        tilde.extend(&(1u64 << 0x30 | 1u64 << 0x31).to_le_bytes());
+        tilde.extend(&0u64.to_le_bytes());
+        tilde.extend(&[1, 0, 0, 0, 2, 0, 0, 0]);
+        tilde.extend(&[13, 0, 0, 0, 0, 0, 0, 0]);
+        tilde.extend(&[1, 0, 18, 0, 0, 0, 0, 0]);
+        let tilde = pad(tilde);
+
+        let mut blob = vec![0, 3, b's', b'r', b'c', 7];
+        blob.extend(b"Main.cs");
+        blob.extend(&[4, b'/', 0, 1, 5]);
+        blob.extend(&[14, 0, 0, 0, 10, 5, 9, 3, 0, 0, 2, 1, 0x7D, 0x04, 0x79]);
+        let blob = pad(blob);
+
+        let streams: Vec<(&str, Vec<u8>)> = vec![("#Pdb", pdb), ("#~", tilde), ("#Strings", vec![0; 4]), ("#Blob", blob), ("#GUID", vec![])];
+        let mut header = b"BSJB".to_vec();
+        header.extend(&[1, 0, 1, 0, 0, 0, 0, 0, 12, 0, 0, 0]);
+        header.extend(b"PDB v1.0\0\0\0\0");
+        header.extend(&[0, 0, streams.len() as u8, 0]);
+        let names: Vec<Vec<u8>> = streams.iter().map(|(name, _)| pad([name.as_bytes(), &[0]].concat())).collect();
+        let mut offset = header.len() + names.iter().map(|n| n.len() + 8).sum::<usize>();
+        let mut body: Vec<u8> = Vec::new();
+        for ((_, data), name) in streams.iter().zip(&names) {
+            header.extend(&(offset as u32).to_le_bytes());
+            header.extend(&(data.len() as u32).to_le_bytes());
+            header.extend(name);
+            body.extend(data);
+            offset += data.len();
+        }
+        header.extend(body);
+        header
+    }
+
+    #[test]
+    fn test_portable_pdb() {
+        let data = portable_pdb();
+        let pdb = PdbFile::from_bytes(&data).unwrap();
+        assert_eq!(pdb.pdb_stream.entry_point, MetadataToken(0x0600_0001));
+        assert_eq!(pdb.pdb_stream.type_system_table_rows[CLITableId::MethodDef as usize], 2);
+        assert_eq!(pdb.tbl_method_debug_information.row, 2);
+        assert_eq!(pdb.document_name(1).unwrap(), "/src/Main.cs");
+
+        let method = MetadataToken::new(CLITableId::MethodDef, 1);
+        let points = pdb.sequence_points(method).unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0], SequencePoint { document: 1, il_offset: 0, start_line: 5, start_column: 9, end_line: 5, end_column: 19 });
+        assert!(points[1].is_hidden());
+        assert_eq!(points[2], SequencePoint { document: 1, il_offset: 5, start_line: 7, start_column: 5, end_line: 8, end_column: 3 });
+
+        let location = pdb.source_location(method, 4).unwrap().unwrap();
+        assert_eq!(location, SourceLocation { file: "/src/Main.cs".to_string(), line: 5, column: 9 });
+        assert_eq!(pdb.source_location(method, 0x20).unwrap().unwrap().line, 7);
+        assert_eq!(pdb.source_location(MetadataToken::new(CLITableId::MethodDef, 2), 0).unwrap(), None);
+        assert!(pdb.sequence_points(MetadataToken::new(CLITableId::TypeDef, 1)).is_err());
+
+        //second point moves ten lines above line 5
+        let blob = [0x00, 0x00, 0x01, 0x02, 0x05, 0x01, 0x02, 0x01, 0x02, 0x6D, 0x00];
+        assert!(decode_sequence_points(&mut BinaryReader::new(&blob), 1).is_err());
+        //hidden points whose IL offsets add up past u32
+        let mut blob = vec![0x00];
+        for _ in 0..9 {
+            blob.extend_from_slice(&[0xDF, 0xFF, 0xFF, 0xFF, 0x00, 0x00]);
+        }
+        assert!(decode_sequence_points(&mut BinaryReader::new(&blob), 1).is_err());
+
+        let compressed = miniz_oxide::deflate::compress_to_vec(&data, 6);
+        let embedded = PdbFile::from_embedded(data.len() as u32, &compressed).unwrap();
+        assert_eq!(embedded.document_name(1).unwrap(), "/src/Main.cs");
+        assert!(PdbFile::from_embedded(data.len() as u32 + 1, &compressed).is_err());
+
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        assert!(dll.embedded_pdb().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_member_lists() {
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        assert_eq!(cli.meta.tables_stream().unwrap().name, "#~");
+        assert_eq!(cli.type_methods(1), Vec::<u32>::new());
+        assert_eq!(cli.type_methods(2), vec![1, 2]);
+        assert_eq!(cli.type_methods(3), Vec::<u32>::new());
+
+        //uncompressed layout, where the method lists index MethodPtr
+        let mut cli = CLIData::default();
+        cli.tbl_typedef.data = vec![MetaTypeDef { method_list: 1, ..Default::default() }, MetaTypeDef { method_list: 3, ..Default::default() }];
+        cli.tbl_typedef.row = 2;
+        cli.tbl_methoddef.row = 3;
+        cli.tbl_method_ptr.data = vec![MetaMethodPtr { method: 3 }, MetaMethodPtr { method: 1 }, MetaMethodPtr { method: 2 }];
+        cli.tbl_method_ptr.row = 3;
+        assert_eq!(cli.type_methods(1), vec![3, 1]);
+        assert_eq!(cli.type_methods(2), vec![2]);
+        match cli.resolve(MetadataToken::new(CLITableId::MethodPtr, 2)).unwrap() {
+            TableRow::MethodPtr(p) => assert_eq!(p.method, 1),
+            row => panic!("{:?}", row),
+        };
+
+        //TestDll.dll rewritten as #- metadata whose MethodPtr lists Main's methods backwards
+        let mut data = std::fs::read("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        let (off, size) = cli.meta.get_stream_rva("#~");
+        let start = cli.meta.meta_pos + off;
+        let stream = data[start..start + size].to_vec();
+        let valid = cli.tilde_stream.valid;
+        let before = (valid & ((1 << CLITableId::MethodPtr as u64) - 1)).count_ones() as usize;
+        let rows_end = 24 + 4 * valid.count_ones() as usize;
+        let method_def = cli.tilde_stream.get_table_pos(CLITableId::MethodDef) - start;
+
+        let mut tables = stream[..8].to_vec();
+        tables.extend_from_slice(&(valid | 1 << CLITableId::MethodPtr as u64).to_le_bytes());
+        tables.extend_from_slice(&stream[16..24 + 4 * before]);
+        tables.extend_from_slice(&2u32.to_le_bytes());
+        tables.extend_from_slice(&stream[24 + 4 * before..method_def]);
+        tables.extend_from_slice(&[2, 0, 1, 0]);
+        tables.extend_from_slice(&stream[method_def..]);
+        assert!(rows_end < method_def);
+
+        let name = start - off + data[start - off..].windows(4).position(|w| w == b"#~\0\0").unwrap();
+        let new_off = (data.len() - cli.meta.meta_pos) as u32;
+        data[name - 8..name - 4].copy_from_slice(&new_off.to_le_bytes());
+        data[name - 4..name].copy_from_slice(&(tables.len() as u32).to_le_bytes());
+        data[name + 1] = b'-';
+        data.extend_from_slice(&tables);
+
+        let patched = DllFile::from_bytes(&data).unwrap();
+        let cli = &patched.clidata;
+        assert_eq!(cli.meta.tables_stream().unwrap().name, "#-");
+        assert_eq!(cli.tbl_method_ptr.row, 2);
+        assert_eq!(cli.tbl_methoddef.row, 2);
+        assert_eq!(cli.type_methods(2), vec![2, 1]);
+        assert_eq!(patched.string(cli.tbl_methoddef.get_row(cli.type_methods(2)[0]).unwrap().name).unwrap(), ".ctor");
+    }
+
+    #[test]
+    fn test_type_sig() {
+        fn parse(bytes: &[u8]) -> TypeSig {
+            let reader = &mut BinaryReader::new(bytes);
+            let sig = TypeSig::parse_signature(reader, bytes.len()).unwrap();
+            assert_eq!(reader.remaining(), 0);
+            sig
+        }
+        use ElementType as E;
+
+        //Dictionary<string, List<int>>[]
+        let sig = parse(&[0x1D, 0x15, 0x12, 0x0D, 0x02, 0x0E, 0x15, 0x12, 0x11, 0x01, 0x08]);
+        let list = TypeSig::GenericInst { is_value_type: false, generic_type: TypeDefOrRef::TypeRef(4), args: vec![TypeSig::Primitive(E::I4)] };
+        let dict = TypeSig::GenericInst { is_value_type: false, generic_type: TypeDefOrRef::TypeRef(3), args: vec![TypeSig::Primitive(E::String), list] };
+        assert_eq!(sig, TypeSig::SZArray(Box::new(dict)));
+
+        let shape = ArrayShape { rank: 2, sizes: vec![3, 4], lo_bounds: vec![-1] };
+        assert_eq!(parse(&[0x14, 0x08, 0x02, 0x02, 0x03, 0x04, 0x01, 0x7F]), TypeSig::Array(Box::new(TypeSig::Primitive(E::I4)), shape));
+        assert_eq!(parse(&[0x0F, 0x01]), TypeSig::Ptr(Box::new(TypeSig::Primitive(E::Void))));
+        assert_eq!(parse(&[0x13, 0x00]), TypeSig::Var(0));
+        assert_eq!(parse(&[0x1E, 0x01]), TypeSig::MVar(1));
+        assert_eq!(parse(&[0x11, 0x08]), TypeSig::ValueType(TypeDefOrRef::TypeDef(2)));
+        match parse(&[0x1B, 0x00, 0x01, 0x08, 0x0A]) {
+            TypeSig::FnPtr(m) => assert_eq!(m.params[0].type_sig, TypeSig::Primitive(E::I8)),
+            sig => panic!("{:?}", sig),
+        };
+        assert!(TypeSig::parse_signature(&mut BinaryReader::new(&[0x12, 0x00]), 2).is_err());
+
+        //nesting is capped instead of overflowing the stack
+        let mut bytes = vec![0x0F; 200_000];
+        bytes.push(0x08);
+        assert!(matches!(TypeSig::parse_signature(&mut BinaryReader::new(&bytes), bytes.len()),
+            Err(Error::InvalidValue { what: "signature nesting", .. })));
+        let mut bytes = vec![0x0F; MAX_SIGNATURE_DEPTH as usize];
+        bytes.push(0x08);
+        assert!(TypeSig::parse_signature(&mut BinaryReader::new(&bytes), bytes.len()).is_ok());
+        //method pointers returning method pointers
+        let mut bytes = [0x1B, 0x00, 0x00].repeat(10_000);
+        bytes.push(0x01);
+        assert!(TypeSig::parse_signature(&mut BinaryReader::new(&bytes), bytes.len()).is_err());
+
+        //instance void M<T>(!!0, ref TypeRef1)
+        let bytes = [0x30, 0x01, 0x02, 0x01, 0x1E, 0x00, 0x10, 0x12, 0x05];
+        let reader = &mut BinaryReader::new(&bytes);
+        let method = MethodDefSig::parse_signature(reader, bytes.len()).unwrap();
+        assert_eq!(reader.remaining(), 0);
+        assert!(method.has_this);
+        assert_eq!(method.generic_param_count, 1);
+        assert_eq!(method.ret_type.type_sig, TypeSig::Primitive(E::Void));
+        assert_eq!(method.params[0].type_sig, TypeSig::MVar(0));
+        assert!(method.params[1].by_ref);
+        assert_eq!(method.params[1].type_sig, TypeSig::Class(TypeDefOrRef::TypeRef(1)));
+
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        let add = cli.tbl_methoddef.get_data_by_filter(&|m: &MetaMethodDef| dll.string(m.name).unwrap() == "add").unwrap();
+        let sig: MethodDefSig = cli.parse_signature(dll.bytes(), add.signature).unwrap();
+        assert_eq!(sig.ret_type.type_sig, TypeSig::Primitive(E::I4));
+        assert!(sig.params.iter().all(|p| p.type_sig == TypeSig::Primitive(E::I4)));
+    }
+
+    #[test]
+    fn test_custom_mods() {
+        use ElementType as E;
+        let modreq = CustomMod { required: true, modifier: TypeDefOrRef::TypeRef(1) };
+        let modopt = CustomMod { required: false, modifier: TypeDefOrRef::TypeRef(2) };
+
+        //in int, modreq(IsReadOnlyAttribute) int&
+        let reader = &mut BinaryReader::new(&[0x1F, 0x05, 0x10, 0x08]);
+        let param = Param::parse_signature(reader, 4).unwrap();
+        assert_eq!(param, Param { custom_mods: vec![modreq.clone()], by_ref: true, type_sig: TypeSig::Primitive(E::I4) });
+
+        //int modopt(IsVolatile)[]
+        let reader = &mut BinaryReader::new(&[0x1D, 0x20, 0x09, 0x08]);
+        let modified = TypeSig::Modified { custom_mods: vec![modopt.clone()], inner: Box::new(TypeSig::Primitive(E::I4)) };
+        assert_eq!(TypeSig::parse_signature(reader, 4).unwrap(), TypeSig::SZArray(Box::new(modified)));
+
+        let reader = &mut BinaryReader::new(&[0x45, 0x10, 0x03]);
+        let local = LocalVar::parse_signature(reader, 3).unwrap();
+        assert!(local.pinned && local.by_ref);
+        assert_eq!(local.type_sig, TypeSig::Primitive(E::Char));
+        let reader = &mut BinaryReader::new(&[0x20, 0x09, 0x45, 0x1F, 0x05, 0x08]);
+        let local = LocalVar::parse_signature(reader, 6).unwrap();
+        assert!(local.pinned && !local.by_ref);
+        assert_eq!(local.custom_mods, vec![modopt, modreq]);
+
+        //vararg void(int, ..., string)
+        let bytes = [0x05, 0x02, 0x01, 0x08, 0x41, 0x0E];
+        let reader = &mut BinaryReader::new(&bytes);
+        let call_site = MethodDefSig::parse_signature(reader, bytes.len()).unwrap();
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(call_site.def_type, MethodDefSigType::VarArg);
+        assert_eq!(call_site.sentinel, Some(1));
+        assert_eq!(call_site.params[1].type_sig, TypeSig::Primitive(E::String));
+
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        let main = cli.tbl_typedef.get_data_by_filter(&|t| dll.string(t.name).unwrap() == "Main").unwrap();
+        assert_eq!(cli.type_name(dll.bytes(), main.base_type().unwrap()).unwrap().unwrap(), "System.Object");
+        assert_eq!(cli.type_name(dll.bytes(), TypeDefOrRef::TypeSpec(1)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_member_sigs() {
+        use ElementType as E;
+        //volatile int
+        let bytes = [0x06, 0x1F, 0x09, 0x08];
+        let field = FieldSig::parse_signature(&mut BinaryReader::new(&bytes), bytes.len()).unwrap();
+        assert_eq!(field.custom_mods.len(), 1);
+        assert_eq!(field.type_sig, TypeSig::Primitive(E::I4));
+        assert!(FieldSig::parse_signature(&mut BinaryReader::new(&[0x07, 0x08]), 2).is_err());
+
+        //instance string this[int, object]
+        let bytes = [0x28, 0x02, 0x0E, 0x08, 0x1C];
+        let property = PropertySig::parse_signature(&mut BinaryReader::new(&bytes), bytes.len()).unwrap();
+        assert!(property.has_this);
+        assert_eq!(property.type_sig, TypeSig::Primitive(E::String));
+        assert_eq!(property.params.len(), 2);
+        assert_eq!(property.params[1].type_sig, TypeSig::Primitive(E::Object));
+
+        //int, pinned char&
+        let bytes = [0x07, 0x02, 0x08, 0x45, 0x10, 0x03];
+        let locals = LocalVarSig::parse_signature(&mut BinaryReader::new(&bytes), bytes.len()).unwrap();
+        assert_eq!(locals.locals.len(), 2);
+        assert!(locals.locals[1].pinned);
+
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        assert!(cli.local_var_sig(dll.bytes(), MetadataToken::default()).unwrap().is_none());
+        let sig = cli.local_var_sig(dll.bytes(), MetadataToken::new(CLITableId::StandAloneSig, 1)).unwrap().unwrap();
+        assert_eq!(sig.locals[0].type_sig, TypeSig::Primitive(E::I4));
+        assert!(cli.local_var_sig(dll.bytes(), MetadataToken::new(CLITableId::Field, 1)).is_err());
+    }
+
+    #[test]
+    fn test_member_ref_sig() {
+        use ElementType as E;
+        let parse = |bytes: &[u8]| MemberRefSig::parse_signature(&mut BinaryReader::new(bytes), bytes.len()).unwrap();
+
+        match parse(&[0x06, 0x0E]) {
+            MemberRefSig::Field(field) => assert_eq!(field.type_sig, TypeSig::Primitive(E::String)),
+            sig => panic!("expect field ref, got {:?}", sig),
+        }
+
+        //call site vararg void(int, ..., string) against vararg void(int)
+        let call_site = match parse(&[0x05, 0x02, 0x01, 0x08, 0x41, 0x0E]) {
+            MemberRefSig::Method(sig) => sig,
+            sig => panic!("expect method ref, got {:?}", sig),
+        };
+        assert_eq!(call_site.fixed_params().len(), 1);
+        assert_eq!(call_site.vararg_params()[0].type_sig, TypeSig::Primitive(E::String));
+        let def = MethodDefSig::parse_signature(&mut BinaryReader::new(&[0x05, 0x01, 0x01, 0x08]), 4).unwrap();
+        assert!(def.vararg_params().is_empty());
+        assert!(def.matches_call_site(&call_site));
+        let fixed = MethodDefSig::parse_signature(&mut BinaryReader::new(&[0x00, 0x01, 0x01, 0x08]), 4).unwrap();
+        assert!(!fixed.matches_call_site(&call_site));
+
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        assert!(cli.tbl_member_ref.row > 0);
+        for rid in 1..=cli.tbl_member_ref.row {
+            let member = cli.tbl_member_ref.get_row(rid).unwrap();
+            match cli.member_ref_sig(dll.bytes(), rid).unwrap() {
+                MemberRefSig::Method(sig) if dll.string(member.name).unwrap() == ".ctor" => {
+                    assert!(sig.has_this);
+                    assert_eq!(sig.ret_type.type_sig, TypeSig::Primitive(E::Void));
+                }
+                _ => (),
+            }
+            if let Some(MemberRefParent::TypeRef(_)) = member.parent() {
+                assert_eq!(cli.resolve_method_ref(dll.bytes(), rid).unwrap(), None);
+            }
+        }
+        assert!(cli.member_ref_sig(dll.bytes(), cli.tbl_member_ref.row + 1).is_err());
+    }
+
+    #[test]
+    fn test_generic_instance() {
+        use ElementType as E;
+        let int = TypeSig::Primitive(E::I4);
+        let string = TypeSig::Primitive(E::String);
+
+        //List<int>
+        let list = TypeSig::parse_signature(&mut BinaryReader::new(&[0x15, 0x12, 0x05, 0x01, 0x08]), 5).unwrap();
+        let (definition, args) = list.generic_instance().unwrap();
+        assert_eq!(definition, TypeDefOrRef::TypeRef(1));
+        assert_eq!(args, &[int.clone()][..]);
+        assert_eq!(int.generic_instance(), None);
+
+        //<string, !0>
+        let spec = MethodSpecSig::parse_signature(&mut BinaryReader::new(&[0x0A, 0x02, 0x0E, 0x13, 0x00]), 5).unwrap();
+        assert_eq!(spec.args, vec![string.clone(), TypeSig::Var(0)]);
+        assert!(MethodSpecSig::parse_signature(&mut BinaryReader::new(&[0x06, 0x00]), 2).is_err());
+
+        //T0 M<T0, T1>(IEnumerable<!0>, T1[]) with the class argument and only the first method argument
+        let bytes = [0x10, 0x02, 0x02, 0x1E, 0x00, 0x15, 0x12, 0x05, 0x01, 0x13, 0x00, 0x1D, 0x1E, 0x01];
+        let sig = MethodDefSig::parse_signature(&mut BinaryReader::new(&bytes), bytes.len()).unwrap();
+        let inst = sig.substitute(&[int.clone()], &[string.clone()]);
+        assert_eq!(inst.ret_type.type_sig, string);
+        assert_eq!(inst.params[0].type_sig, TypeSig::GenericInst {
+            is_value_type: false,
+            generic_type: TypeDefOrRef::TypeRef(1),
+            args: vec![int],
+        });
+        assert_eq!(inst.params[1].type_sig, TypeSig::SZArray(Box::new(TypeSig::MVar(1))));
+        assert_eq!(sig.substitute(&[], &[]), sig);
+
+        let dll = load_dll("./assets/TestDll.dll").unwrap();
+        let cli = &dll.clidata;
+        assert!(cli.type_spec(dll.bytes(), cli.tbl_type_spec.row + 1).is_err());
+        assert!(cli.method_spec(dll.bytes(), cli.tbl_method_spec.row + 1).is_err());
+        assert!(cli.member_ref_type_args(dll.bytes(), 1).unwrap().is_empty());
+    }
+}
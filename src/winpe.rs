@@ -1,8 +1,40 @@
 
 use crate::reader::*;
+use crate::error::*;
 
-#[derive(Debug)]
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Default)]
+pub enum PeMagic{
+    #[default]
+    PE32 = 0x10B,
+    PE32Plus = 0x20B,
+}
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Machine{
+    Unknown = 0x0,
+    I386 = 0x14C,
+    ARMNT = 0x1C4,
+    IA64 = 0x200,
+    AMD64 = 0x8664,
+    ARM64 = 0xAA64,
+}
+
+impl From<u16> for Machine{
+    fn from(v: u16) -> Self {
+        match v {
+            0x14C => Machine::I386,
+            0x1C4 => Machine::ARMNT,
+            0x200 => Machine::IA64,
+            0x8664 => Machine::AMD64,
+            0xAA64 => Machine::ARM64,
+            _ => Machine::Unknown,
+        }
+    }
+}
+
+#[derive(Debug,Default)]
 pub struct WinPe{
+    pub machine:u16,
     pub num_section:u16,
     pub time_date_stamp:u32,
     pub pointer_sbl_tbl:u32,
@@ -18,68 +50,163 @@ pub struct WinPe{
     pub base_of_code:u32,
     pub base_of_data:u32,
 
+    pub magic:PeMagic,
+    pub image_base:u64,
+    pub section_alignment:u32,
+    pub file_alignment:u32,
+    pub size_of_image:u32,
+    pub size_of_headers:u32,
+    pub checksum:u32,
+    pub subsystem:u16,
+    pub dll_characteristics:u16,
+    //4 bytes each in PE32, 8 in PE32+
+    pub size_of_stack_reserve:u64,
+    pub size_of_stack_commit:u64,
+    pub size_of_heap_reserve:u64,
+    pub size_of_heap_commit:u64,
+    pub num_rva_and_sizes:u32,
+
+    //file offsets of the optional header and its data directory array
+    pub opt_header_offset:usize,
+    pub data_directory_offset:usize,
+
+    //data directories
+    pub export_tbl:DataPointer,
+    pub import_tbl:DataPointer,
+    pub resource_tbl:DataPointer,
+    pub exception_tbl:DataPointer,
+    pub certificate_tbl:DataPointer,
+    pub base_relocation_tbl:DataPointer,
+    pub debug:DataPointer,
+    pub architecture_data:DataPointer,
+    pub global_ptr:DataPointer,
+    pub tls_tbl:DataPointer,
+    pub load_config_tbl:DataPointer,
+    pub bound_import:DataPointer,
+    pub import_addr_tbl:DataPointer,
+    pub delay_import_descriptor:DataPointer,
+    pub clr_runtime_header:DataPointer,
+    pub sections:Vec<SectionHeader>,
+}
+
+#[derive(Debug,Default,Clone)]
+pub struct SectionHeader{
+    pub name:String,
+    pub virtual_size:u32,
+    pub virtual_addr:u32,
+    pub size_of_raw_data:u32,
+    pub pointer_to_raw_data:u32,
+    pub pointer_to_relocations:u32,
+    pub pointer_to_linenumbers:u32,
+    pub num_of_relocations:u16,
+    pub num_of_linenumbers:u16,
+    pub characteristics:u32,
+}
+
+impl SectionHeader{
+    /// size of the section once loaded, falls back to the raw size for linkers that leave virtual size zero
+    pub fn mapped_size(&self)->u32{
+        if self.virtual_size == 0 {
+            self.size_of_raw_data
+        }else{
+            self.virtual_size
+        }
+    }
+
+    pub fn contains_rva(&self,rva:u32)->bool{
+        rva >= self.virtual_addr && rva - self.virtual_addr < self.mapped_size()
+    }
 }
 
 impl WinPe{
 
-    pub fn parse_winpe(reader:&mut BinaryReader)->WinPe{
+    pub fn parse_winpe(reader:&mut BinaryReader)->Result<WinPe>{
 
         //dos header
-        reader.tag(&[0x4D,0x5A]);
-        reader.ate(62);
+        let dos_pos = reader.pos;
+        reader.expect_tag(&[0x4D,0x5A])?;
+        reader.ate(58)?;
+        let pe_offset = reader.le_u32()?;
 
         //dos stub
-        reader.ate(64);
+        reader.seek(dos_pos + pe_offset as usize);
 
         //COFF header
-        reader.tag(&[0x50,0x45,0,0]);
-        let _machine = reader.le_u16();
-        let num_section = reader.le_u16();
-        let time_date_stamp = reader.le_u32();
-        let pointer_sbl_tbl = reader.le_u32();
-        let _num_sbl_tbl = reader.le_u32();
-        let sz_opt_header =reader.le_u16();
-        let characteristics = reader.le_u16();
+        reader.expect_tag(&[0x50,0x45,0,0])?;
+        let machine = reader.le_u16()?;
+        let num_section = reader.le_u16()?;
+        let time_date_stamp = reader.le_u32()?;
+        let pointer_sbl_tbl = reader.le_u32()?;
+        let _num_sbl_tbl = reader.le_u32()?;
+        let sz_opt_header =reader.le_u16()?;
+        let characteristics = reader.le_u16()?;
 
         //COFF field
-        reader.tag(&[0x0B,0x01]);
-        let maj_linker_ver = reader.le_u8();
-        let min_linker_ver = reader.le_u8();
-        let size_code = reader.le_u32();
-        let size_initialized_data = reader.le_u32();
-        let size_uninitialized_data = reader.le_u32();
-        let addr_entry_point = reader.le_u32();
-        let base_of_code = reader.le_u32();
-        let base_of_data = reader.le_u32();
+        let opt_header_pos = reader.pos;
+        let magic = match reader.le_u16()? {
+            0x10B => PeMagic::PE32,
+            0x20B => PeMagic::PE32Plus,
+            v => return Err(Error::InvalidValue { what: "optional header magic", value: v as u32 }),
+        };
+        let maj_linker_ver = reader.le_u8()?;
+        let min_linker_ver = reader.le_u8()?;
+        let size_code = reader.le_u32()?;
+        let size_initialized_data = reader.le_u32()?;
+        let size_uninitialized_data = reader.le_u32()?;
+        let addr_entry_point = reader.le_u32()?;
+        let base_of_code = reader.le_u32()?;
+        //PE32+ drops base_of_data and widens image_base
+        let (base_of_data,image_base) = match magic {
+            PeMagic::PE32 => (reader.le_u32()?,reader.le_u32()? as u64),
+            PeMagic::PE32Plus => (0,reader.le_u64()?),
+        };
+
         //pe nt field
-        reader.ate(68);
+        let section_alignment = reader.le_u32()?;
+        let file_alignment = reader.le_u32()?;
+        reader.ate(16)?;
+        let size_of_image = reader.le_u32()?;
+        let size_of_headers = reader.le_u32()?;
+        let checksum = reader.le_u32()?;
+        let subsystem = reader.le_u16()?;
+        let dll_characteristics = reader.le_u16()?;
+        //stack and heap reserve/commit
+        let mut sizes = [0u64;4];
+        for size in sizes.iter_mut() {
+            *size = match magic {
+                PeMagic::PE32 => reader.le_u32()? as u64,
+                PeMagic::PE32Plus => reader.le_u64()?,
+            };
+        }
+        let [size_of_stack_reserve,size_of_stack_commit,size_of_heap_reserve,size_of_heap_commit] = sizes;
+        let _loader_flags = reader.le_u32()?;
+        let num_rva_and_sizes = reader.le_u32()?;
 
         //data directories
-        let _export_tbl = reader.data_pointer();
-        let _import_tbl = reader.data_pointer();
-        let _resource_tbl = reader.data_pointer();
-        let _exception_tbl = reader.data_pointer();
-        let _certificate_tbl = reader.data_pointer();
-        let _base_relocation_tbl =reader.data_pointer();
-        let _debug = reader.data_pointer();
-        let _architecture_data = reader.data_pointer();
-        let _global_ptr = reader.le_u32();
-        reader.ate(4);
-
-        let _tls_tbl = reader.data_pointer();
-        let _load_config_tbl = reader.data_pointer();
-        let _bound_import = reader.data_pointer();
-        let _import_addr_tbl = reader.data_pointer();
-        let _delay_import_descriptor = reader.data_pointer();
-        let _clr_runtime_helper = reader.data_pointer();
-        reader.ate(8);
+        let data_directory_offset = reader.pos;
+        let dir = |reader:&mut BinaryReader,index:u32| WinPe::parse_data_directory(reader,index,num_rva_and_sizes);
+        let export_tbl = dir(reader,0)?;
+        let import_tbl = dir(reader,1)?;
+        let resource_tbl = dir(reader,2)?;
+        let exception_tbl = dir(reader,3)?;
+        let certificate_tbl = dir(reader,4)?;
+        let base_relocation_tbl = dir(reader,5)?;
+        let debug = dir(reader,6)?;
+        let architecture_data = dir(reader,7)?;
+        let global_ptr = dir(reader,8)?;
+        let tls_tbl = dir(reader,9)?;
+        let load_config_tbl = dir(reader,10)?;
+        let bound_import = dir(reader,11)?;
+        let import_addr_tbl = dir(reader,12)?;
+        let delay_import_descriptor = dir(reader,13)?;
+        let clr_runtime_header = dir(reader,14)?;
 
         //sections
-        let _text_section = WinPe::parse_section(reader);
-        let _rsrc_section = WinPe::parse_section(reader);
-        let _reloc_section = WinPe::parse_section(reader);
+        reader.seek(opt_header_pos + sz_opt_header as usize);
+        let sections = reader.repeat(WinPe::parse_section,num_section as u32)?;
 
-        WinPe{
+        Ok(WinPe{
+            machine,
             num_section,
             time_date_stamp,
             pointer_sbl_tbl,
@@ -92,20 +219,108 @@ impl WinPe{
             size_uninitialized_data,
             addr_entry_point,
             base_of_code,
-            base_of_data
+            base_of_data,
+            magic,
+            image_base,
+            section_alignment,
+            file_alignment,
+            size_of_image,
+            size_of_headers,
+            checksum,
+            subsystem,
+            dll_characteristics,
+            size_of_stack_reserve,
+            size_of_stack_commit,
+            size_of_heap_reserve,
+            size_of_heap_commit,
+            num_rva_and_sizes,
+            opt_header_offset:opt_header_pos,
+            data_directory_offset,
+            export_tbl,
+            import_tbl,
+            resource_tbl,
+            exception_tbl,
+            certificate_tbl,
+            base_relocation_tbl,
+            debug,
+            architecture_data,
+            global_ptr,
+            tls_tbl,
+            load_config_tbl,
+            bound_import,
+            import_addr_tbl,
+            delay_import_descriptor,
+            clr_runtime_header,
+            sections,
+        })
+    }
+
+    fn parse_data_directory(reader:&mut BinaryReader,index:u32,count:u32)->ReadResult<DataPointer>{
+        if index < count {
+            reader.data_pointer()
+        }else{
+            Ok(DataPointer::default())
+        }
+    }
+
+    fn parse_section(reader:&mut BinaryReader)->Result<SectionHeader>{
+        let raw_name = reader.bytes(8)?;
+        let name_len = raw_name.iter().position(|&b| b == 0).unwrap_or(8);
+        let name = String::from_utf8_lossy(&raw_name[..name_len]).into_owned();
+
+        Ok(SectionHeader{
+            name,
+            virtual_size:reader.le_u32()?,
+            virtual_addr:reader.le_u32()?,
+            size_of_raw_data:reader.le_u32()?,
+            pointer_to_raw_data:reader.le_u32()?,
+            pointer_to_relocations:reader.le_u32()?,
+            pointer_to_linenumbers:reader.le_u32()?,
+            num_of_relocations:reader.le_u16()?,
+            num_of_linenumbers:reader.le_u16()?,
+            characteristics:reader.le_u32()?,
+        })
+    }
+
+    pub fn machine_type(&self)->Machine{
+        Machine::from(self.machine)
+    }
+
+    /// machine with the target OS that ReadyToRun images XOR into it removed
+    pub fn native_machine(&self)->Machine{
+        //linux, apple, freebsd, netbsd
+        for os in [0u16, 0x7B79, 0x4644, 0xADC4, 0x1993].iter() {
+            let machine = Machine::from(self.machine ^ os);
+            if machine != Machine::Unknown {
+                return machine;
+            }
         }
+        Machine::Unknown
+    }
+
+    pub fn is_pe32_plus(&self)->bool{
+        self.magic == PeMagic::PE32Plus
     }
 
-    fn parse_section(reader:&mut BinaryReader){
-        let _virtual_size = reader.le_u32();
-        let _virtual_addr = reader.le_u32();
-        let _size_of_raw_data = reader.le_u32();
-        let _pointer_to_raw_data = reader.le_u32();
-        let _pointer_to_relocations = reader.le_u32();
-        let _pointer_to_linenumbers = reader.le_u32();
-        let _num_of_relocations = reader.le_u16();
-        let _num_of_linenumbers = reader.le_u16();
-        let _characteristics = reader.le_u32();
+    pub fn get_section_by_rva(&self,rva:u32)->Option<&SectionHeader>{
+        self.sections.iter().find(|s| s.contains_rva(rva))
+    }
+
+    /// resolve a relative virtual address to an offset in the image file
+    pub fn rva_to_offset(&self,rva:u32)->Result<usize>{
+        let section = match self.get_section_by_rva(rva) {
+            Some(s)=>s,
+            None=>return Err(Error::InvalidRva(rva)),
+        };
+        let delta = rva - section.virtual_addr;
+        if delta >= section.size_of_raw_data {
+            //uninitialized tail of the section, not backed by file data
+            return Err(Error::InvalidRva(rva));
+        }
+        match section.pointer_to_raw_data.checked_add(delta) {
+            Some(offset)=>Ok(offset as usize),
+            None=>Err(Error::InvalidRva(rva)),
+        }
     }
 }
 